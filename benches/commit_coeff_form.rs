@@ -1,3 +1,6 @@
+// the benchmarks only need parameters, not secure ones
+#![allow(deprecated)]
+
 use blstrs::Scalar;
 use kzg::polynomial::Polynomial;
 use kzg::{coeff_form::KZGProver, setup, KZGParams};
@@ -25,7 +28,7 @@ fn bench_commit<const NUM_COEFFS: usize>(c: &mut Criterion) {
         format!("bench_commit_coeff_form, degree {}", NUM_COEFFS - 1).as_str(),
         |b| {
             b.iter(|| {
-                black_box(&prover).commit(black_box(&polynomial))
+                black_box(&prover).commit(black_box(&polynomial), None).unwrap()
             })
         },
     );
@@ -1,3 +1,6 @@
+// the benchmarks only need parameters, not secure ones
+#![allow(deprecated)]
+
 use blstrs::Scalar;
 use kzg::{eval_form::{compute_lagrange_basis, KZGProverEvalForm}, ft::EvaluationDomain, setup, KZGParams};
 use pairing::group::ff::Field;
@@ -23,7 +26,7 @@ fn random_evals(rng: &mut SmallRng, d: usize) -> EvaluationDomain {
 fn bench_commit<const NUM_COEFFS: usize>(c: &mut Criterion) {
     let mut rng = SmallRng::from_seed([42; 32]);
     let params = test_setup(&mut rng, NUM_COEFFS);
-    let lagrange_basis = compute_lagrange_basis(&params);
+    let lagrange_basis = compute_lagrange_basis(&params).unwrap();
 
     let evals = random_evals(&mut rng, NUM_COEFFS);
     let prover = KZGProverEvalForm::new(&params, lagrange_basis.0.as_slice());
@@ -32,7 +35,7 @@ fn bench_commit<const NUM_COEFFS: usize>(c: &mut Criterion) {
         format!("bench_commit_eval_form, degree {}", NUM_COEFFS - 1).as_str(),
         |b| {
             b.iter(|| {
-                prover.commit(black_box(&evals))
+                prover.commit(black_box(&evals)).unwrap()
             })
         },
     );
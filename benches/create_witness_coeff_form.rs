@@ -1,3 +1,6 @@
+// the benchmarks only need parameters, not secure ones
+#![allow(deprecated)]
+
 use blstrs::Scalar;
 use kzg::polynomial::Polynomial;
 use kzg::{coeff_form::KZGProver, setup, KZGParams};
@@ -20,14 +23,14 @@ fn bench_create_witness<const NUM_COEFFS: usize>(c: &mut Criterion) {
     }
     let polynomial = Polynomial::new_from_coeffs(coeffs, NUM_COEFFS - 1);
     let prover = KZGProver::new(&params);
-    let _commitment = prover.commit(&polynomial);
+    let _commitment = prover.commit(&polynomial, None).unwrap().0;
 
     let x: Scalar = Scalar::random(&mut rng);
     let y = polynomial.eval(x);
     
     c.bench_function(
         format!("bench_create_witness_coeff_form, degree {}", NUM_COEFFS - 1).as_str(),
-        |b| b.iter(|| black_box(&prover).create_witness(black_box(&polynomial), black_box((x, y))).unwrap()),
+        |b| b.iter(|| black_box(&prover).create_witness(black_box(&polynomial), black_box((x, y)), None).unwrap()),
     );
 
     let mut xs = Vec::with_capacity(NUM_COEFFS - 1);
@@ -1,3 +1,6 @@
+// the benchmarks only need parameters, not secure ones
+#![allow(deprecated)]
+
 use blstrs::Scalar;
 use kzg::ft::EvaluationDomain;
 use kzg::{eval_form::KZGProverEvalForm, setup, eval_form::compute_lagrange_basis, KZGParams};
@@ -24,15 +27,15 @@ fn random_evals(rng: &mut SmallRng, d: usize) -> EvaluationDomain {
 fn bench_create_witness<const NUM_COEFFS: usize>(c: &mut Criterion) {
     let mut rng = SmallRng::from_seed([42; 32]);
     let params = test_setup(&mut rng, NUM_COEFFS);
-    let lagrange_basis = compute_lagrange_basis(&params);
+    let lagrange_basis = compute_lagrange_basis(&params).unwrap();
 
     let evals = random_evals(&mut rng, NUM_COEFFS);
     let prover = KZGProverEvalForm::new(&params, lagrange_basis.0.as_slice());
-    let _commitment = prover.commit(&evals);
+    let _commitment = prover.commit(&evals).unwrap();
 
     c.bench_function(
         format!("bench_create_witness_eval_form, degree {}", NUM_COEFFS - 1).as_str(),
-        |b| b.iter(|| black_box(&prover).create_witness(black_box(&evals), black_box(5))),
+        |b| b.iter(|| black_box(&prover).create_witness(black_box(&evals), black_box(5)).unwrap()),
     );
 }
 
@@ -1,3 +1,6 @@
+// the benchmarks only need parameters, not secure ones
+#![allow(deprecated)]
+
 use blstrs::Scalar;
 use kzg::polynomial::Polynomial;
 use kzg::{
@@ -23,8 +26,8 @@ fn bench_poly_verify<const NUM_COEFFS: usize>(c: &mut Criterion) {
     }
     let polynomial = Polynomial::new_from_coeffs(coeffs, NUM_COEFFS - 1);
     let prover = KZGProver::new(&params);
-    let verifier = KZGVerifier::new(&params);
-    let commitment = prover.commit(&polynomial);
+    let verifier = KZGVerifier::new(&params).unwrap();
+    let commitment = prover.commit(&polynomial, None).unwrap().0;
 
     c.bench_function(
         format!("bench_verify_poly_coeff_form, degree {}", NUM_COEFFS - 1).as_str(),
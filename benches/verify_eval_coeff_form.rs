@@ -1,3 +1,6 @@
+// the benchmarks only need parameters, not secure ones
+#![allow(deprecated)]
+
 use blstrs::Scalar;
 use kzg::polynomial::Polynomial;
 use kzg::{
@@ -23,12 +26,12 @@ fn bench_verify_eval<const NUM_COEFFS: usize>(c: &mut Criterion) {
     }
     let polynomial = Polynomial::new_from_coeffs(coeffs, NUM_COEFFS - 1);
     let prover = KZGProver::new(&params);
-    let verifier = KZGVerifier::new(&params);
-    let commitment = prover.commit(&polynomial);
+    let verifier = KZGVerifier::new(&params).unwrap();
+    let commitment = prover.commit(&polynomial, None).unwrap().0;
 
     let x: Scalar = rng.gen::<u64>().into();
     let y = polynomial.eval(x);
-    let witness = prover.create_witness(&polynomial, (x, y)).unwrap();
+    let witness = prover.create_witness(&polynomial, (x, y), None).unwrap().0;
 
     c.bench_function(
         format!("bench_verify_eval_coeff_form, degree {}", NUM_COEFFS - 1).as_str(),
@@ -38,6 +41,7 @@ fn bench_verify_eval<const NUM_COEFFS: usize>(c: &mut Criterion) {
                     black_box((x, y)),
                     black_box(&commitment),
                     black_box(&witness),
+                    None,
                 )
             })
         },
@@ -0,0 +1,270 @@
+//! Multi-party "powers of tau" ceremony for `KZGParams`.
+//!
+//! A ceremony starts from the trivial parameters (tau = 1, see `initial_params`). Each participant
+//! samples a fresh secret `r`, multiplies `gs[i]` and `hs[i]` by `r^i` and publishes the new
+//! parameters together with an `UpdateProof`. As long as a single participant forgets their `r`,
+//! nobody knows the final tau.
+//!
+//! The proof of knowledge of `r` follows Bowe-Gabizon-Miers: the participant publishes `H^r` for a
+//! point `H` in G2 obtained by hashing `g^r` and the previous `gs[1]` to the curve, which can be
+//! checked against `g^r` with a single pairing equation.
+
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Scalar};
+use pairing::group::{ff::Field, prime::PrimeCurveAffine, Curve, Group};
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "parallel")]
+use crate::utils::chunk_by_num_threads;
+use crate::{pairings_equal, zeroize_scalar, KZGError, KZGParams};
+
+const POK_DST: &[u8] = b"KZG_CEREMONY_POK_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+/// published by each participant alongside the updated parameters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateProof {
+    /// gs[1] after the update, i.e. g^(tau * r). Also serves as the "running product" of the chain.
+    pub tau_g1: G1Affine,
+    /// g^r
+    pub r_g1: G1Affine,
+    /// h^r - the update witness. e(tau_g1, h) == e(previous tau_g1, h^r)
+    pub r_g2: G2Affine,
+    /// H^r where H = hash_to_curve(r_g1 || previous tau_g1) - proof of knowledge of r
+    pub pok: G2Affine,
+}
+
+impl UpdateProof {
+    fn pok_base(r_g1: &G1Affine, prev_tau_g1: &G1Affine) -> G2Affine {
+        let mut msg = Vec::with_capacity(96);
+        msg.extend_from_slice(&r_g1.to_compressed());
+        msg.extend_from_slice(&prev_tau_g1.to_compressed());
+        G2Projective::hash_to_curve(&msg, POK_DST, &[]).to_affine()
+    }
+
+    /// checks this proof against `prev_tau_g1`, the gs[1] of the parameters it was applied to
+    pub fn verify(&self, prev_tau_g1: &G1Affine) -> Result<(), KZGError> {
+        if bool::from(self.r_g1.is_identity()) || bool::from(self.tau_g1.is_identity()) {
+            return Err(KZGError::InvalidUpdate);
+        }
+
+        let g = G1Affine::generator();
+        let h: G2Prepared = G2Affine::generator().into();
+        let r_g2: G2Prepared = self.r_g2.into();
+
+        // proof of knowledge of r
+        let base: G2Prepared = Self::pok_base(&self.r_g1, prev_tau_g1).into();
+        if !pairings_equal(&self.r_g1, &base, &g, &self.pok.into()) {
+            return Err(KZGError::InvalidProofOfKnowledge);
+        }
+
+        // g^r and h^r have the same exponent
+        if !pairings_equal(&self.r_g1, &h, &g, &r_g2) {
+            return Err(KZGError::InvalidUpdate);
+        }
+
+        // the new tau is the old tau times r
+        if !pairings_equal(&self.tau_g1, &h, prev_tau_g1, &r_g2) {
+            return Err(KZGError::InvalidUpdate);
+        }
+
+        Ok(())
+    }
+}
+
+/// the parameters a ceremony starts from - every power is the generator, i.e. tau = 1
+pub fn initial_params(num_coeffs: usize) -> KZGParams {
+    KZGParams {
+        gs: vec![G1Projective::generator(); num_coeffs],
+        hs: vec![G2Projective::generator(); num_coeffs],
+    }
+}
+
+/// contributes a fresh secret drawn from `rng` to `params`. `params` must have at least two G1
+/// powers.
+pub fn contribute<R: RngCore + CryptoRng>(
+    params: &KZGParams,
+    rng: &mut R,
+) -> Result<(KZGParams, UpdateProof), KZGError> {
+    let mut r = Scalar::random(&mut *rng);
+    while bool::from(r.is_zero()) {
+        r = Scalar::random(&mut *rng);
+    }
+
+    let res = contribute_with_secret(params, &r);
+    zeroize_scalar(&mut r);
+    res
+}
+
+/// contributes `r` to `params`. Callers are responsible for sampling `r` uniformly and forgetting it
+/// afterwards - prefer `contribute` unless `r` comes from somewhere else (e.g. an HSM).
+/// Returns `KZGError::InvalidUpdate` if `r` is zero or `params` has fewer than two G1 powers.
+pub fn contribute_with_secret(
+    params: &KZGParams,
+    r: &Scalar,
+) -> Result<(KZGParams, UpdateProof), KZGError> {
+    if params.gs.len() < 2 || bool::from(r.is_zero()) {
+        return Err(KZGError::InvalidUpdate);
+    }
+
+    let prev_tau_g1 = params.gs[1].to_affine();
+
+    let mut gs = params.gs.clone();
+    let mut hs = params.hs.clone();
+    mul_by_powers(&mut gs, r);
+    mul_by_powers(&mut hs, r);
+
+    let r_g1 = (G1Projective::generator() * r).to_affine();
+    let proof = UpdateProof {
+        tau_g1: gs[1].to_affine(),
+        r_g1,
+        r_g2: (G2Projective::generator() * r).to_affine(),
+        pok: (UpdateProof::pok_base(&r_g1, &prev_tau_g1) * r).to_affine(),
+    };
+
+    Ok((KZGParams { gs, hs }, proof))
+}
+
+/// verifies a single update from `prev` to `next`
+pub fn verify_update<R: RngCore>(
+    prev: &KZGParams,
+    next: &KZGParams,
+    proof: &UpdateProof,
+    rng: &mut R,
+) -> Result<(), KZGError> {
+    if prev.gs.len() != next.gs.len() || prev.hs.len() != next.hs.len() || prev.gs.len() < 2 {
+        return Err(KZGError::InvalidUpdate);
+    }
+
+    proof.verify(&prev.gs[1].to_affine())?;
+    if next.gs[1].to_affine() != proof.tau_g1 {
+        return Err(KZGError::InvalidUpdate);
+    }
+
+    next.verify(rng)
+}
+
+/// verifies that `params` is the result of applying every update in `proofs`, in order,
+/// to `initial_params(params.gs.len())`.
+pub fn verify_chain<R: RngCore>(
+    params: &KZGParams,
+    proofs: &[UpdateProof],
+    rng: &mut R,
+) -> Result<(), KZGError> {
+    if params.gs.len() < 2 || proofs.is_empty() {
+        return Err(KZGError::InvalidUpdate);
+    }
+
+    let mut prev_tau_g1 = G1Affine::generator();
+    for proof in proofs {
+        proof.verify(&prev_tau_g1)?;
+        prev_tau_g1 = proof.tau_g1;
+    }
+
+    if params.gs[1].to_affine() != prev_tau_g1 {
+        return Err(KZGError::InvalidUpdate);
+    }
+
+    params.verify(rng)
+}
+
+/// multiplies `points[i]` by `r^i`, zeroizing the running power
+fn mul_by_powers<G: Group<Scalar = Scalar>>(points: &mut [G], r: &Scalar) {
+    #[cfg(feature = "parallel")]
+    rayon::scope(|scope| {
+        let chunk_size = chunk_by_num_threads(points.len());
+
+        for (i, chunk) in points.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(move |_scope| {
+                let mut u = r.pow_vartime([(i * chunk_size) as u64]);
+                for p in chunk.iter_mut() {
+                    *p *= u;
+                    u *= r;
+                }
+                zeroize_scalar(&mut u);
+            });
+        }
+    });
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        let mut u = Scalar::one();
+        for p in points.iter_mut() {
+            *p *= u;
+            u *= r;
+        }
+        zeroize_scalar(&mut u);
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::setup;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const RNG_SEED: [u8; 32] = [69; 32];
+
+    #[test]
+    fn test_chain() {
+        let mut rng = StdRng::from_seed(RNG_SEED);
+        let mut params = initial_params(16);
+        let mut proofs = Vec::new();
+
+        for _ in 0..3 {
+            let (next, proof) = contribute(&params, &mut rng).unwrap();
+            assert!(verify_update(&params, &next, &proof, &mut rng).is_ok());
+
+            params = next;
+            proofs.push(proof);
+        }
+
+        assert!(verify_chain(&params, &proofs, &mut rng).is_ok());
+
+        // dropping a contribution breaks the chain
+        assert!(verify_chain(&params, &proofs[1..], &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_contribution_matches_setup() {
+        let r1 = Scalar::from(7);
+        let r2 = Scalar::from(11);
+
+        let (params, _) = contribute_with_secret(&initial_params(8), &r1).unwrap();
+        let (params, _) = contribute_with_secret(&params, &r2).unwrap();
+
+        let expected = setup(r1 * r2, 8);
+        assert_eq!(params.gs, expected.gs);
+        assert_eq!(params.hs, expected.hs);
+
+        assert!(matches!(
+            contribute_with_secret(&params, &Scalar::zero()),
+            Err(KZGError::InvalidUpdate)
+        ));
+        assert!(matches!(
+            contribute_with_secret(&initial_params(1), &r1),
+            Err(KZGError::InvalidUpdate)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_update() {
+        let mut rng = StdRng::from_seed(RNG_SEED);
+        let params = initial_params(8);
+        let (mut next, proof) = contribute(&params, &mut rng).unwrap();
+
+        // a proof of knowledge for a different base point is rejected
+        let mut bad_proof = proof;
+        bad_proof.pok = (G2Projective::generator() * Scalar::from(3)).to_affine();
+        assert!(matches!(
+            verify_update(&params, &next, &bad_proof, &mut rng),
+            Err(KZGError::InvalidProofOfKnowledge)
+        ));
+
+        // a single power that isn't consistent with the rest is rejected
+        next.gs[5] += G1Projective::generator();
+        assert!(matches!(
+            verify_update(&params, &next, &proof, &mut rng),
+            Err(KZGError::MalformedParams)
+        ));
+    }
+}
@@ -1,12 +1,21 @@
-use blstrs::{pairing, G1Affine, G1Projective, G2Projective, Scalar};
+use blstrs::{G1Affine, G1Projective, G2Prepared, G2Projective, Scalar};
 use pairing::group::{ff::Field, prime::PrimeCurveAffine, Curve};
+use rand_core::RngCore;
 use std::fmt::Debug;
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
+use crate::fixed_base::{scalar_powers, FixedBaseTable};
+use crate::fk20::FK20Table;
 use crate::polynomial::{op_tree, Polynomial, SubProductTree};
-use crate::{KZGCommitment, KZGError, KZGParams, KZGWitness};
+use crate::transcript::Transcript;
+use crate::{
+    check_g2_powers, pairings_equal, zeroize_scalar, KZGCommitment, KZGError, KZGParams, KZGWitness,
+    PreparedG2, SrsBackend,
+};
+
+const HIDING_GENERATOR_DST: &[u8] = b"KZG_HIDING_GENERATOR_BLS12381G1_XMD:SHA-256_SSWU_RO_";
 
 // A witness for a several elements - "w_B" in the paper. It's a single group element plus a polynomial
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,58 +43,248 @@ impl KZGBatchWitness {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct KZGProver<'params> {
-    parameters: &'params KZGParams,
+/// A compact witness for several elements - just "w_B", without the interpolation polynomial. The
+/// verifier recomputes the polynomial from the evaluations, so the witness is a single group element
+/// no matter how many points are opened (see `KZGVerifier::verify_eval_batched_compact`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct KZGCompactBatchWitness {
+    w: G1Affine,
+}
+
+impl KZGCompactBatchWitness {
+    pub fn elem(&self) -> G1Affine {
+        self.w
+    }
+
+    pub fn elem_ref(&self) -> &G1Affine {
+        &self.w
+    }
+
+    pub fn new(w: G1Affine) -> Self {
+        KZGCompactBatchWitness { w }
+    }
+}
+
+impl From<KZGBatchWitness> for KZGCompactBatchWitness {
+    fn from(witness: KZGBatchWitness) -> Self {
+        KZGCompactBatchWitness { w: witness.w }
+    }
+}
+
+/// A witness for several polynomials, each opened at its own set of points (BDFG20, a.k.a. SHPLONK).
+/// It's two group elements no matter how many polynomials and points there are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct KZGMultiPointWitness {
+    w: G1Affine,
+    w_prime: G1Affine,
+}
+
+impl KZGMultiPointWitness {
+    pub fn w(&self) -> G1Affine {
+        self.w
+    }
+
+    pub fn w_prime(&self) -> G1Affine {
+        self.w_prime
+    }
+
+    pub fn new(w: G1Affine, w_prime: G1Affine) -> Self {
+        KZGMultiPointWitness { w, w_prime }
+    }
+}
+
+#[derive(Debug)]
+pub struct KZGProver<'params, P: SrsBackend + ?Sized = KZGParams> {
+    parameters: &'params P,
 }
 
-#[derive(Debug, Clone)]
-pub struct KZGVerifier<'params> {
-    parameters: &'params KZGParams,
+#[derive(Debug)]
+pub struct KZGVerifier<'params, P: SrsBackend + ?Sized = KZGParams> {
+    parameters: &'params P,
+    prepared: PreparedG2,
 }
 
-impl<'params> KZGProver<'params> {
+impl<'params, P: SrsBackend + ?Sized> Clone for KZGProver<'params, P> {
+    fn clone(&self) -> Self {
+        KZGProver { parameters: self.parameters }
+    }
+}
+
+impl<'params, P: SrsBackend + ?Sized> Clone for KZGVerifier<'params, P> {
+    fn clone(&self) -> Self {
+        KZGVerifier {
+            parameters: self.parameters,
+            prepared: self.prepared.clone(),
+        }
+    }
+}
+
+impl<'params, P: SrsBackend + ?Sized> KZGProver<'params, P> {
     /// initializes `polynomial` to zero polynomial
-    pub fn new(parameters: &'params KZGParams) -> Self {
+    pub fn new(parameters: &'params P) -> Self {
         Self {
             parameters,
         }
     }
 
-    pub fn parameters(&self) -> &'params KZGParams {
+    pub fn parameters(&self) -> &'params P {
         self.parameters
     }
 
-    pub fn commit(&self, polynomial: &Polynomial) -> KZGCommitment {
-        let gs = &self.parameters.gs[..polynomial.num_coeffs()];
-        let commitment = G1Projective::multi_exp(gs, polynomial.slice_coeffs());
+    /// commits to `polynomial`. If `degree_bound` is `Some(d)`, also commits to the shifted
+    /// polynomial `X^(N - d) * polynomial`, where `N = gs.len() - 1` is the largest degree the
+    /// parameters support. Opening both at the same point convinces the verifier that the polynomial
+    /// has degree at most `d` (Marlin-style degree bounds, see `KZGVerifier::verify_eval`).
+    pub fn commit(
+        &self,
+        polynomial: &Polynomial,
+        degree_bound: Option<usize>,
+    ) -> Result<(KZGCommitment, Option<KZGCommitment>), KZGError> {
+        let gs = self.parameters.g1_powers();
+        let commitment = commit_to(gs, polynomial)?;
+        let shifted = match degree_bound {
+            None => None,
+            Some(d) => Some(commit_to(gs, &shift_for_bound(polynomial, d, gs.len())?)?),
+        };
 
-        commitment.to_affine()
+        Ok((commitment, shifted))
     }
 
-    pub fn create_witness(&self, polynomial: &Polynomial, (x, y): (Scalar, Scalar)) -> Result<KZGWitness, KZGError> {
-        let mut dividend = polynomial.clone();
-        dividend.coeffs[0] -= y;
+    /// creates a witness for `polynomial(x) = y`. If `degree_bound` is `Some(d)`, also creates a
+    /// witness for the shifted polynomial committed to by `commit` at the same point.
+    pub fn create_witness(
+        &self,
+        polynomial: &Polynomial,
+        (x, y): (Scalar, Scalar),
+        degree_bound: Option<usize>,
+    ) -> Result<(KZGWitness, Option<KZGWitness>), KZGError> {
+        let gs = self.parameters.g1_powers();
+        let witness = open(gs, polynomial, (x, y))?;
+        let shifted = match degree_bound {
+            None => None,
+            Some(d) => {
+                let shifted = shift_for_bound(polynomial, d, gs.len())?;
+                let shift = (gs.len() - 1 - d) as u64;
+                Some(open(gs, &shifted, (x, x.pow_vartime([shift]) * y))?)
+            }
+        };
 
-        let divisor = Polynomial::new_from_coeffs(vec![-x, Scalar::one()], 1);
-        match dividend.long_division(&divisor) {
-            // by polynomial remainder theorem, if (x - point.x) does not divide self.polynomial, then
-            // self.polynomial(point.y) != point.1
-            (_, Some(_)) => Err(KZGError::PointNotOnPolynomial),
-            (psi, None) if psi.num_coeffs() == 1 => Ok((self.parameters.gs[0] * psi.coeffs[0]).to_affine()),
-            (psi, None) => {
-                let gs = &self.parameters.gs[..psi.num_coeffs()];
-                Ok(G1Projective::multi_exp(gs, psi.slice_coeffs()).to_affine())
+        Ok((witness, shifted))
+    }
+
+    /// returns the witnesses for `polynomial` at every point of the subgroup of size `d`, in order
+    /// of `omega^i`, using FK20 (see `fk20`). `d` must be a power of two, or this returns
+    /// `UnsupportedDomain(d)`, and `polynomial` must have at most `d` coefficients. When opening
+    /// many polynomials, build an `FK20Table` once instead.
+    pub fn create_all_witnesses(&self, polynomial: &Polynomial, d: usize) -> Result<Vec<KZGWitness>, KZGError> {
+        FK20Table::new(self.parameters, d)?.open_all(polynomial, Scalar::one())
+    }
+
+    /// opens every polynomial at the same point `x` with a single witness (GWC19). Returns the
+    /// evaluations, in the same order as `polynomials`, and a witness for the random linear
+    /// combination `sum gamma^i * f_i`, where `gamma` is squeezed from `transcript` after absorbing
+    /// the commitments, `x` and the evaluations.
+    pub fn create_witness_multi(
+        &self,
+        polynomials: &[(Polynomial, KZGCommitment)],
+        x: Scalar,
+        transcript: &mut Transcript,
+    ) -> Result<(Vec<Scalar>, KZGWitness), KZGError> {
+        if polynomials.is_empty() {
+            return Err(KZGError::NoPolynomial);
+        }
+
+        let ys: Vec<Scalar> = polynomials.iter().map(|(f, _)| f.eval(x)).collect();
+        let commitments: Vec<KZGCommitment> = polynomials.iter().map(|(_, c)| *c).collect();
+        let gamma = multi_opening_challenge(transcript, &commitments, x, &ys);
+
+        let gammas = powers_of(gamma, polynomials.len());
+        let f = linear_combination(polynomials.iter().map(|(f, _)| f), &gammas);
+        let y = ys.iter().zip(gammas.iter()).fold(Scalar::zero(), |acc, (y, g)| acc + y * g);
+
+        let witness = open(self.parameters.g1_powers(), &f, (x, y))?;
+        Ok((ys, witness))
+    }
+
+    /// opens every polynomial at its own set of points with a single two-element witness (BDFG20).
+    /// `openings` holds each polynomial, its commitment and the points to open it at. Returns the
+    /// evaluations at each polynomial's points, in the same order, and the witness. Both challenges
+    /// are squeezed from `transcript`.
+    pub fn create_witness_multi_point(
+        &self,
+        openings: &[(Polynomial, KZGCommitment, Vec<Scalar>)],
+        transcript: &mut Transcript,
+    ) -> Result<(Vec<Vec<Scalar>>, KZGMultiPointWitness), KZGError> {
+        if openings.is_empty() {
+            return Err(KZGError::NoPolynomial);
+        }
+
+        let ys: Vec<Vec<Scalar>> = openings
+            .iter()
+            .map(|(f, _, xs)| xs.iter().map(|&x| f.eval(x)).collect())
+            .collect();
+
+        // f_i - r_i, and the quotients (f_i - r_i) / Z_{S_i}
+        let mut numerators = Vec::with_capacity(openings.len());
+        let mut quotients = Vec::with_capacity(openings.len());
+        let mut interpolations = Vec::with_capacity(openings.len());
+        for ((f, _, xs), ys) in openings.iter().zip(ys.iter()) {
+            if xs.is_empty() {
+                return Err(KZGError::NoPolynomial);
             }
+
+            let tree = SubProductTree::new_from_points(xs);
+            let r = interpolate(xs, ys, &tree);
+            let numerator = f - &r;
+            match numerator.long_division(&tree.product) {
+                (q, None) => quotients.push(q),
+                (_, Some(_)) => return Err(KZGError::PointNotOnPolynomial),
+            }
+
+            numerators.push(numerator);
+            interpolations.push(r);
         }
+
+        let commitments: Vec<KZGCommitment> = openings.iter().map(|(_, c, _)| *c).collect();
+        let xs: Vec<&[Scalar]> = openings.iter().map(|(_, _, xs)| xs.as_slice()).collect();
+        let gamma = multi_point_challenge(transcript, &commitments, &xs, &ys);
+        let gammas = powers_of(gamma, openings.len());
+
+        // h = sum gamma^i (f_i - r_i) / Z_{S_i}
+        let h = linear_combination(quotients.iter(), &gammas);
+        let w = commit_to(self.parameters.g1_powers(), &h)?;
+
+        // L = sum gamma^i Z_{T \ S_i}(z) (f_i - r_i(z)) - Z_T(z) h, which vanishes at z
+        let z = multi_point_evaluation_challenge(transcript, &w);
+        let (zs, z_t) = vanishing_evals(&xs, z);
+        let coeffs: Vec<Scalar> = gammas.iter().zip(zs.iter()).map(|(g, z_i)| g * z_i).collect();
+
+        let mut l = linear_combination(openings.iter().map(|(f, _, _)| f), &coeffs);
+        let r_z = interpolations
+            .iter()
+            .zip(coeffs.iter())
+            .fold(Scalar::zero(), |acc, (r, c)| acc + r.eval(z) * c);
+        l.coeffs[0] -= r_z;
+        let l = &l - &h.scalar_multiplication(z_t);
+
+        let w_prime = open(self.parameters.g1_powers(), &l, (z, Scalar::zero()))?;
+
+        Ok((ys, KZGMultiPointWitness { w, w_prime }))
     }
 
+    /// returns `KZGError::NoPolynomial` if `xs` is empty
     pub fn create_witness_batched(
         &self,
         polynomial: &Polynomial,
         xs: &[Scalar],
         ys: &[Scalar],
     ) -> Result<KZGBatchWitness, KZGError> {
+        if xs.is_empty() {
+            return Err(KZGError::NoPolynomial);
+        }
+
         let tree = SubProductTree::new_from_points(xs);
 
         let interpolation = Polynomial::lagrange_interpolation_with_tree(xs, ys, &tree);
@@ -94,98 +293,632 @@ impl<'params> KZGProver<'params> {
         let (psi, rem) = numerator.long_division(&tree.product);
         match rem {
             Some(_) => Err(KZGError::PointNotOnPolynomial),
-            None => {
-                let w = if psi.num_coeffs() == 1 {
-                    self.parameters.gs[0] * psi.coeffs[0]
-                } else {
-                    let gs = &self.parameters.gs[..psi.num_coeffs()];
-                    G1Projective::multi_exp(gs, psi.slice_coeffs())
-                };
-
-                Ok(KZGBatchWitness {
-                    r: interpolation,
-                    w: w.to_affine(),
-                })
-            }
+            None => Ok(KZGBatchWitness {
+                r: interpolation,
+                w: commit_to(self.parameters.g1_powers(), &psi)?,
+            }),
         }
     }
+
+    /// like `create_witness_batched`, but leaves out the interpolation polynomial
+    pub fn create_compact_witness_batched(
+        &self,
+        polynomial: &Polynomial,
+        xs: &[Scalar],
+        ys: &[Scalar],
+    ) -> Result<KZGCompactBatchWitness, KZGError> {
+        self.create_witness_batched(polynomial, xs, ys).map(Into::into)
+    }
 }
 
-impl<'params> KZGVerifier<'params> {
-    pub fn new(parameters: &'params KZGParams) -> Self {
-        KZGVerifier { parameters }
+impl<'params, P: SrsBackend + ?Sized> KZGVerifier<'params, P> {
+    /// needs at least two G2 powers, returns `KZGError::NotEnoughG2Powers` otherwise
+    pub fn new(parameters: &'params P) -> Result<Self, KZGError> {
+        Ok(KZGVerifier {
+            parameters,
+            prepared: PreparedG2::new(parameters.g2_powers())?,
+        })
     }
 
     pub fn verify_poly(&self, commitment: &KZGCommitment, polynomial: &Polynomial) -> bool {
-        let gs = &self.parameters.gs[..polynomial.num_coeffs()];
+        let gs = &self.parameters.g1_powers()[..polynomial.num_coeffs()];
         let check = G1Projective::multi_exp(gs, polynomial.slice_coeffs());
 
         check.to_affine() == *commitment
     }
 
+    /// checks that `commitment` opens to `y` at `x`. If `degree_bound` is
+    /// `Some((d, shifted, shifted_witness))`, with the shifted commitment and witness from
+    /// `KZGProver::commit` and `KZGProver::create_witness`, also checks that `shifted` opens to
+    /// `x^(N - d) * y`, where `N = gs.len() - 1`. Nothing of degree above `N` can be committed to, so
+    /// this means the polynomial has degree at most `d`, as long as `x` was picked after both
+    /// commitments (e.g. squeezed from a `Transcript`). Only needs `hs[0]` and `hs[1]` either way.
     pub fn verify_eval(
         &self,
         (x, y): (Scalar, Scalar),
         commitment: &KZGCommitment,
         witness: &KZGWitness,
+        degree_bound: Option<(usize, &KZGCommitment, &KZGWitness)>,
     ) -> bool {
-        let lhs = pairing(
-            witness,
-            &(self.parameters.hs[1] - self.parameters.hs[0] * x).to_affine(),
-        );
-        let rhs = pairing(
-            &(commitment.to_curve() - self.parameters.gs[0] * y).to_affine(),
-            &self.parameters.hs[0].to_affine(),
-        );
+        let gs = self.parameters.g1_powers();
+        if !verify_opening(&self.prepared, gs[0], (x, y), commitment, witness) {
+            return false;
+        }
+
+        match degree_bound {
+            None => true,
+            Some((d, _, _)) if d >= gs.len() => false,
+            Some((d, shifted, shifted_witness)) => {
+                let shift = (gs.len() - 1 - d) as u64;
+                verify_opening(&self.prepared, gs[0], (x, x.pow_vartime([shift]) * y), shifted, shifted_witness)
+            }
+        }
+    }
+
+    /// verifies many independent single-point openings at once. Each `((x, y), commitment, witness)`
+    /// is what would be passed to `verify_eval`. The checks are combined with random weights drawn
+    /// from `rng` into two MSMs and a single pairing equation, so a batch containing an invalid
+    /// opening passes with probability at most `1/|F|`. To make the check deterministic, absorb
+    /// every opening into a `Transcript` and pass `Transcript::challenge_rng` as `rng`.
+    pub fn verify_eval_many<R: RngCore>(
+        &self,
+        openings: &[((Scalar, Scalar), KZGCommitment, KZGWitness)],
+        rng: &mut R,
+    ) -> bool {
+        verify_openings_batched(
+            self.parameters.g1_powers()[0],
+            &self.prepared,
+            openings.iter().map(|&((x, y), c, w)| (x, y, c, w)),
+            rng,
+        )
+    }
+
+    /// verifies a witness from `KZGProver::create_witness_multi` for polynomials with the given
+    /// commitments and evaluations at `x`, with a single pairing check. `transcript` must be in the
+    /// same state as the prover's was.
+    pub fn verify_eval_multi(
+        &self,
+        x: Scalar,
+        commitments_and_evals: &[(KZGCommitment, Scalar)],
+        witness: &KZGWitness,
+        transcript: &mut Transcript,
+    ) -> bool {
+        let commitments: Vec<KZGCommitment> = commitments_and_evals.iter().map(|(c, _)| *c).collect();
+        let ys: Vec<Scalar> = commitments_and_evals.iter().map(|(_, y)| *y).collect();
+        let gamma = multi_opening_challenge(transcript, &commitments, x, &ys);
+
+        let gammas = powers_of(gamma, ys.len());
+        let y = ys.iter().zip(gammas.iter()).fold(Scalar::zero(), |acc, (y, g)| acc + y * g);
+
+        let commitments: Vec<G1Projective> = commitments.iter().map(|c| c.to_curve()).collect();
+        let commitment = G1Projective::multi_exp(&commitments, &gammas);
+
+        self.verify_eval((x, y), &commitment.to_affine(), witness, None)
+    }
+
+    /// verifies a witness from `KZGProver::create_witness_multi_point`. `openings` holds each
+    /// commitment with its points and evaluations. Only needs `hs[0]` and `hs[1]`. `transcript` must
+    /// be in the same state as the prover's was.
+    pub fn verify_eval_multi_point(
+        &self,
+        openings: &[(KZGCommitment, Vec<Scalar>, Vec<Scalar>)],
+        witness: &KZGMultiPointWitness,
+        transcript: &mut Transcript,
+    ) -> bool {
+        if openings.iter().any(|(_, xs, ys)| xs.is_empty() || xs.len() != ys.len()) {
+            return false;
+        }
 
-        lhs == rhs
+        let commitments: Vec<KZGCommitment> = openings.iter().map(|(c, _, _)| *c).collect();
+        let xs: Vec<&[Scalar]> = openings.iter().map(|(_, xs, _)| xs.as_slice()).collect();
+        let ys: Vec<Vec<Scalar>> = openings.iter().map(|(_, _, ys)| ys.clone()).collect();
+        let gamma = multi_point_challenge(transcript, &commitments, &xs, &ys);
+        let gammas = powers_of(gamma, openings.len());
+
+        let z = multi_point_evaluation_challenge(transcript, &witness.w);
+        let (zs, z_t) = vanishing_evals(&xs, z);
+        let coeffs: Vec<Scalar> = gammas.iter().zip(zs.iter()).map(|(g, z_i)| g * z_i).collect();
+
+        let r_z = openings
+            .iter()
+            .zip(coeffs.iter())
+            .fold(Scalar::zero(), |acc, ((_, xs, ys), c)| {
+                let tree = SubProductTree::new_from_points(xs);
+                acc + interpolate(xs, ys, &tree).eval(z) * c
+            });
+
+        // F = [L(tau)] = sum c_i C_i - [sum c_i r_i(z)] - Z_T(z) W
+        let commitments: Vec<G1Projective> = commitments.iter().map(|c| c.to_curve()).collect();
+        let f = G1Projective::multi_exp(&commitments, &coeffs)
+            - self.parameters.g1_powers()[0] * r_z
+            - witness.w.to_curve() * z_t;
+
+        // L(tau) = (tau - z) W'
+        pairings_equal(
+            &(f + witness.w_prime.to_curve() * z).to_affine(),
+            &self.prepared.h,
+            &witness.w_prime,
+            &self.prepared.h_alpha,
+        )
     }
 
+    /// needs `xs.len() + 1` G2 powers, and returns `KZGError::NotEnoughG2Powers` otherwise
     pub fn verify_eval_batched(
         &self,
         xs: &[Scalar],
         commitment: &KZGCommitment,
         witness: &KZGBatchWitness,
-    ) -> bool {
+    ) -> Result<bool, KZGError> {
+        self.verify_batched_opening(xs, &witness.r, commitment, &witness.w)
+    }
+
+    /// like `verify_eval_batched`, but recomputes the interpolation polynomial from `ys`, which are
+    /// the claimed evaluations at `xs`
+    pub fn verify_eval_batched_compact(
+        &self,
+        xs: &[Scalar],
+        ys: &[Scalar],
+        commitment: &KZGCommitment,
+        witness: &KZGCompactBatchWitness,
+    ) -> Result<bool, KZGError> {
+        if xs.is_empty() || xs.len() != ys.len() {
+            return Ok(false);
+        }
+
+        let r = Polynomial::lagrange_interpolation(xs, ys);
+        self.verify_batched_opening(xs, &r, commitment, &witness.w)
+    }
+
+    /// checks `e(w, h^z(alpha)) == e(C - g^r(alpha), h)`, where `z` vanishes on `xs`
+    fn verify_batched_opening(
+        &self,
+        xs: &[Scalar],
+        r: &Polynomial,
+        commitment: &KZGCommitment,
+        w: &G1Affine,
+    ) -> Result<bool, KZGError> {
+        // r comes from the witness, so it may be longer than the parameters allow
+        if xs.is_empty() || r.num_coeffs() > self.parameters.g1_powers().len() {
+            return Ok(false);
+        }
+        check_g2_powers(self.parameters, xs.len() + 1)?;
+
         let z: Polynomial = op_tree(
             xs.len(),
             &|i| {
-                let mut coeffs = vec![-xs[i], Scalar::one()];
-                coeffs[0] = -xs[i];
-                coeffs[1] = Scalar::one();
+                let coeffs = vec![-xs[i], Scalar::one()];
                 Polynomial::new_from_coeffs(coeffs, 1)
             },
             &|a, b| a.best_mul(&b),
         );
 
         let hz = if z.num_coeffs() == 1 {
-            self.parameters.hs[0] * z.coeffs[0]
+            self.parameters.g2_powers()[0] * z.coeffs[0]
         } else {
-            let hs = &self.parameters.hs[..z.num_coeffs()];
+            let hs = &self.parameters.g2_powers()[..z.num_coeffs()];
             G2Projective::multi_exp(hs, z.slice_coeffs())
         };
 
-        let gr = if witness.r.num_coeffs() == 1 {
-            self.parameters.gs[0] * witness.r.coeffs[0]
+        let gr = if r.num_coeffs() == 1 {
+            self.parameters.g1_powers()[0] * r.coeffs[0]
         } else {
-            let gs = &self.parameters.gs[..witness.r.num_coeffs()];
-            G1Projective::multi_exp(gs, witness.r.slice_coeffs())
+            let gs = &self.parameters.g1_powers()[..r.num_coeffs()];
+            G1Projective::multi_exp(gs, r.slice_coeffs())
         };
 
-        let lhs = pairing(&witness.w, &hz.to_affine());
-        let rhs = pairing(
+        let hz: G2Prepared = hz.to_affine().into();
+        Ok(pairings_equal(
+            w,
+            &hz,
             &(commitment.to_curve() - gr).to_affine(),
-            &self.parameters.hs[0].to_affine(),
-        );
+            &self.prepared.h,
+        ))
+    }
+}
+
+/// checks `e(w_j, h^alpha - x_j h) == e(C_j - y_j g, h)` for every `(x_j, y_j, C_j, w_j)` in
+/// `openings`. With random `r_j` this becomes
+/// `e(sum r_j w_j, h^alpha) == e(sum r_j (C_j + x_j w_j) - (sum r_j y_j) g, h)`.
+pub(crate) fn verify_openings_batched<R, I>(g: G1Projective, prepared: &PreparedG2, openings: I, rng: &mut R) -> bool
+where
+    R: RngCore,
+    I: Iterator<Item = (Scalar, Scalar, KZGCommitment, KZGWitness)>,
+{
+    let mut witnesses = Vec::new();
+    let mut ws = Vec::new();
+    let mut points = Vec::new();
+    let mut ps = Vec::new();
+    let mut y = Scalar::zero();
+
+    for (x_j, y_j, c_j, w_j) in openings {
+        let r = Scalar::random(&mut *rng);
+
+        witnesses.push(w_j.to_curve());
+        ws.push(r);
+
+        points.push(c_j.to_curve());
+        ps.push(r);
+        points.push(w_j.to_curve());
+        ps.push(r * x_j);
+
+        y += r * y_j;
+    }
+
+    if witnesses.is_empty() {
+        return true;
+    }
+
+    let lhs = G1Projective::multi_exp(&witnesses, &ws);
+    let rhs = G1Projective::multi_exp(&points, &ps) - g * y;
+
+    pairings_equal(&lhs.to_affine(), &prepared.h_alpha, &rhs.to_affine(), &prepared.h)
+}
+
+/// g^polynomial(alpha)
+fn commit_to(gs: &[G1Projective], polynomial: &Polynomial) -> Result<KZGCommitment, KZGError> {
+    if polynomial.num_coeffs() > gs.len() {
+        return Err(KZGError::PolynomialDegreeTooLarge);
+    }
+
+    let gs = &gs[..polynomial.num_coeffs()];
+    Ok(G1Projective::multi_exp(gs, polynomial.slice_coeffs()).to_affine())
+}
+
+/// the witness g^psi(alpha) for `polynomial(x) = y`, where `psi = (polynomial - y) / (X - x)`
+fn open(gs: &[G1Projective], polynomial: &Polynomial, (x, y): (Scalar, Scalar)) -> Result<KZGWitness, KZGError> {
+    let mut dividend = polynomial.clone();
+    dividend.coeffs[0] -= y;
+
+    let divisor = Polynomial::new_from_coeffs(vec![-x, Scalar::one()], 1);
+    match dividend.long_division(&divisor) {
+        // by polynomial remainder theorem, if (x - point.x) does not divide self.polynomial, then
+        // self.polynomial(point.y) != point.1
+        (_, Some(_)) => Err(KZGError::PointNotOnPolynomial),
+        (psi, None) => commit_to(gs, &psi),
+    }
+}
+
+/// `X^(N - d) * polynomial` for the degree bound `d`, where `N = num_powers - 1`
+fn shift_for_bound(polynomial: &Polynomial, d: usize, num_powers: usize) -> Result<Polynomial, KZGError> {
+    if d >= num_powers {
+        return Err(KZGError::PolynomialDegreeTooLarge);
+    }
+
+    let degree = Polynomial::compute_degree(&polynomial.coeffs, polynomial.degree());
+    if degree > d {
+        return Err(KZGError::DegreeBoundExceeded);
+    }
+
+    let shift = num_powers - 1 - d;
+    let mut coeffs = vec![Scalar::zero(); shift + degree + 1];
+    coeffs[shift..].copy_from_slice(&polynomial.coeffs[..degree + 1]);
+    Ok(Polynomial::new(coeffs))
+}
+
+/// checks `e(w, h^alpha - x h) == e(C - y g, h)`, rearranged as `e(w, h^alpha) == e(C - y g + x w, h)`
+/// so that both sides only pair against the prepared `h` and `h^alpha`
+pub(crate) fn verify_opening(
+    prepared: &PreparedG2,
+    g: G1Projective,
+    (x, y): (Scalar, Scalar),
+    commitment: &KZGCommitment,
+    witness: &KZGWitness,
+) -> bool {
+    let rhs = commitment.to_curve() - g * y + witness.to_curve() * x;
+    pairings_equal(witness, &prepared.h_alpha, &rhs.to_affine(), &prepared.h)
+}
+
+/// the challenge `gamma` for `create_witness_multi`/`verify_eval_multi`
+fn multi_opening_challenge(
+    transcript: &mut Transcript,
+    commitments: &[KZGCommitment],
+    x: Scalar,
+    ys: &[Scalar],
+) -> Scalar {
+    transcript.append_u64(b"num polynomials", commitments.len() as u64);
+    for (c, y) in commitments.iter().zip(ys.iter()) {
+        transcript.append_commitment(b"commitment", c);
+        transcript.append_scalar(b"evaluation", y);
+    }
+    transcript.append_scalar(b"point", &x);
+
+    transcript.challenge_scalar(b"gamma")
+}
+
+/// the challenge `gamma` for `create_witness_multi_point`/`verify_eval_multi_point`
+fn multi_point_challenge(
+    transcript: &mut Transcript,
+    commitments: &[KZGCommitment],
+    xs: &[&[Scalar]],
+    ys: &[Vec<Scalar>],
+) -> Scalar {
+    transcript.append_u64(b"num polynomials", commitments.len() as u64);
+    for ((c, xs), ys) in commitments.iter().zip(xs.iter()).zip(ys.iter()) {
+        transcript.append_commitment(b"commitment", c);
+        transcript.append_scalars(b"points", xs);
+        transcript.append_scalars(b"evaluations", ys);
+    }
+
+    transcript.challenge_scalar(b"gamma")
+}
+
+/// the evaluation point `z` for `create_witness_multi_point`/`verify_eval_multi_point`
+fn multi_point_evaluation_challenge(transcript: &mut Transcript, w: &KZGWitness) -> Scalar {
+    transcript.append_witness(b"quotient commitment", w);
+    transcript.challenge_scalar(b"z")
+}
+
+/// returns `Z_{T \ S_i}(z)` for every set of points `S_i`, and `Z_T(z)`, where `T` is the union
+/// of all the `S_i`
+fn vanishing_evals(xs: &[&[Scalar]], z: Scalar) -> (Vec<Scalar>, Scalar) {
+    let mut t: Vec<Scalar> = Vec::new();
+    for &x in xs.iter().flat_map(|xs| xs.iter()) {
+        if !t.contains(&x) {
+            t.push(x);
+        }
+    }
+
+    let z_t = t.iter().fold(Scalar::one(), |acc, &x| acc * (z - x));
+    let zs = xs
+        .iter()
+        .map(|xs| t.iter().filter(|x| !xs.contains(x)).fold(Scalar::one(), |acc, &x| acc * (z - x)))
+        .collect();
+
+    (zs, z_t)
+}
+
+/// the lowest-degree polynomial through `(xs[i], ys[i])`. `tree` must be the sub-product tree of `xs`.
+pub(crate) fn interpolate(xs: &[Scalar], ys: &[Scalar], tree: &SubProductTree) -> Polynomial {
+    if xs.len() == 1 {
+        Polynomial::from_scalar(ys[0])
+    } else {
+        Polynomial::lagrange_interpolation_with_tree(xs, ys, tree)
+    }
+}
+
+/// `1, x, x^2, ..., x^(n - 1)`
+fn powers_of(x: Scalar, n: usize) -> Vec<Scalar> {
+    let mut powers = Vec::with_capacity(n);
+    let mut curr = Scalar::one();
+    for _ in 0..n {
+        powers.push(curr);
+        curr *= x;
+    }
+    powers
+}
+
+/// `sum coeffs[i] * polynomials[i]`
+fn linear_combination<'a, I: Iterator<Item = &'a Polynomial>>(polynomials: I, coeffs: &[Scalar]) -> Polynomial {
+    let mut res: Vec<Scalar> = vec![Scalar::zero()];
+    for (f, c) in polynomials.zip(coeffs.iter()) {
+        if f.num_coeffs() > res.len() {
+            res.resize(f.num_coeffs(), Scalar::zero());
+        }
+        for (r, f_c) in res.iter_mut().zip(f.iter_coeffs()) {
+            *r += c * f_c;
+        }
+    }
+
+    Polynomial::new(res)
+}
+
+/// the second G1 generator of the hiding variant. It's hashed to the curve, so nobody knows its
+/// discrete log with respect to `g`.
+pub fn hiding_generator() -> G1Projective {
+    G1Projective::hash_to_curve(b"kzg hiding generator", HIDING_GENERATOR_DST, &[])
+}
+
+/// the extra parameters of the hiding variant ("PolyCommit_Ped" in the paper).
+///
+/// **Limitation:** these can only be computed by whoever knows tau, i.e. with
+/// `ToxicWaste::into_params_with_hiding`. Neither `ceremony` nor the Ethereum (`trusted_setup`) or
+/// `.ptau` setups produce powers of tau on the hiding generator, and `KZGParams::verify` doesn't
+/// check them, so the hiding variant can't be used on top of those setups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct KZGHidingParams {
+    /// gamma, gamma^alpha^1, gamma^alpha^2, ... where gamma is `hiding_generator()`
+    pub gammas: Vec<G1Projective>,
+}
+
+impl KZGHidingParams {
+    /// computes the powers of `s` on the hiding generator. `s` has to be the same secret the
+    /// `KZGParams` were generated with. Just as insecure as `setup` - see
+    /// `ToxicWaste::into_params_with_hiding`.
+    #[deprecated(note = "insecure: whoever picks `s` can forge openings. Use `ToxicWaste::into_params_with_hiding`")]
+    pub fn setup(s: Scalar, num_coeffs: usize) -> Self {
+        Self::from_tau(&s, num_coeffs)
+    }
+
+    /// the powers of `tau` on the hiding generator. The powers of `tau` are zeroized before
+    /// returning.
+    pub(crate) fn from_tau(tau: &Scalar, num_coeffs: usize) -> Self {
+        let mut powers = scalar_powers(tau, num_coeffs);
+
+        let table = FixedBaseTable::new(hiding_generator(), FixedBaseTable::<G1Projective>::best_window(num_coeffs));
+        let gammas = table.batch_mul(&powers);
+
+        for power in powers.iter_mut() {
+            zeroize_scalar(power);
+        }
+
+        KZGHidingParams { gammas }
+    }
+}
+
+/// A witness for a single element in the hiding variant - the group element plus the evaluation of
+/// the blinding polynomial at the same point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct KZGHidingWitness {
+    w: G1Affine,
+    blinding_eval: Scalar,
+}
+
+impl KZGHidingWitness {
+    pub fn elem(&self) -> G1Affine {
+        self.w
+    }
+
+    pub fn elem_ref(&self) -> &G1Affine {
+        &self.w
+    }
+
+    pub fn blinding_eval(&self) -> Scalar {
+        self.blinding_eval
+    }
+
+    pub fn new(w: G1Affine, blinding_eval: Scalar) -> Self {
+        KZGHidingWitness { w, blinding_eval }
+    }
+}
+
+#[derive(Debug)]
+pub struct KZGHidingProver<'params, P: SrsBackend + ?Sized = KZGParams> {
+    parameters: &'params P,
+    hiding: &'params KZGHidingParams,
+}
+
+#[derive(Debug)]
+pub struct KZGHidingVerifier<'params, P: SrsBackend + ?Sized = KZGParams> {
+    parameters: &'params P,
+    hiding: &'params KZGHidingParams,
+    prepared: PreparedG2,
+}
+
+impl<'params, P: SrsBackend + ?Sized> Clone for KZGHidingProver<'params, P> {
+    fn clone(&self) -> Self {
+        KZGHidingProver {
+            parameters: self.parameters,
+            hiding: self.hiding,
+        }
+    }
+}
+
+impl<'params, P: SrsBackend + ?Sized> Clone for KZGHidingVerifier<'params, P> {
+    fn clone(&self) -> Self {
+        KZGHidingVerifier {
+            parameters: self.parameters,
+            hiding: self.hiding,
+            prepared: self.prepared.clone(),
+        }
+    }
+}
+
+/// g^polynomial(alpha) + gamma^blinding(alpha). Errors if either polynomial has more coefficients
+/// than there are powers for it.
+fn hiding_commitment(
+    gs: &[G1Projective],
+    gammas: &[G1Projective],
+    polynomial: &Polynomial,
+    blinding: &Polynomial,
+) -> Result<G1Projective, KZGError> {
+    if polynomial.num_coeffs() > gs.len() || blinding.num_coeffs() > gammas.len() {
+        return Err(KZGError::PolynomialDegreeTooLarge);
+    }
+
+    let gs = &gs[..polynomial.num_coeffs()];
+    let gammas = &gammas[..blinding.num_coeffs()];
+    Ok(G1Projective::multi_exp(gs, polynomial.slice_coeffs()) + G1Projective::multi_exp(gammas, blinding.slice_coeffs()))
+}
+
+impl<'params, P: SrsBackend + ?Sized> KZGHidingProver<'params, P> {
+    pub fn new(parameters: &'params P, hiding: &'params KZGHidingParams) -> Self {
+        Self { parameters, hiding }
+    }
+
+    pub fn parameters(&self) -> &'params P {
+        self.parameters
+    }
+
+    /// samples a uniformly random blinding polynomial with `num_coeffs` coefficients. To hide a
+    /// polynomial, its blinding polynomial must have at least as many coefficients.
+    pub fn random_blinding<R: RngCore>(num_coeffs: usize, rng: &mut R) -> Polynomial {
+        let coeffs = (0..num_coeffs).map(|_| Scalar::random(&mut *rng)).collect();
+        Polynomial::new(coeffs)
+    }
+
+    /// returns `KZGError::PolynomialDegreeTooLarge` if `polynomial` or `blinding` has more
+    /// coefficients than there are powers for it
+    pub fn commit(&self, polynomial: &Polynomial, blinding: &Polynomial) -> Result<KZGCommitment, KZGError> {
+        hiding_commitment(self.parameters.g1_powers(), &self.hiding.gammas, polynomial, blinding).map(|c| c.to_affine())
+    }
+
+    pub fn create_witness(
+        &self,
+        polynomial: &Polynomial,
+        blinding: &Polynomial,
+        (x, y): (Scalar, Scalar),
+    ) -> Result<KZGHidingWitness, KZGError> {
+        let divisor = Polynomial::new_from_coeffs(vec![-x, Scalar::one()], 1);
+
+        let mut dividend = polynomial.clone();
+        dividend.coeffs[0] -= y;
+        let psi = match dividend.long_division(&divisor) {
+            (_, Some(_)) => return Err(KZGError::PointNotOnPolynomial),
+            (psi, None) => psi,
+        };
+
+        // the blinding polynomial is opened at whatever it evaluates to, so this always divides
+        let blinding_eval = blinding.eval(x);
+        let mut dividend = blinding.clone();
+        dividend.coeffs[0] -= blinding_eval;
+        let (psi_hat, _) = dividend.long_division(&divisor);
+
+        let w = hiding_commitment(self.parameters.g1_powers(), &self.hiding.gammas, &psi, &psi_hat)?;
+
+        Ok(KZGHidingWitness {
+            w: w.to_affine(),
+            blinding_eval,
+        })
+    }
+}
+
+impl<'params, P: SrsBackend + ?Sized> KZGHidingVerifier<'params, P> {
+    /// needs at least two G2 powers, returns `KZGError::NotEnoughG2Powers` otherwise
+    pub fn new(parameters: &'params P, hiding: &'params KZGHidingParams) -> Result<Self, KZGError> {
+        Ok(KZGHidingVerifier {
+            parameters,
+            hiding,
+            prepared: PreparedG2::new(parameters.g2_powers())?,
+        })
+    }
+
+    pub fn verify_poly(&self, commitment: &KZGCommitment, polynomial: &Polynomial, blinding: &Polynomial) -> bool {
+        match hiding_commitment(self.parameters.g1_powers(), &self.hiding.gammas, polynomial, blinding) {
+            Ok(check) => check.to_affine() == *commitment,
+            Err(_) => false,
+        }
+    }
 
-        lhs == rhs
+    pub fn verify_eval(
+        &self,
+        (x, y): (Scalar, Scalar),
+        commitment: &KZGCommitment,
+        witness: &KZGHidingWitness,
+    ) -> bool {
+        let gamma = match self.hiding.gammas.first() {
+            Some(gamma) => gamma,
+            None => return false,
+        };
+
+        // take the blinding out, and what's left is a regular opening
+        let commitment = commitment.to_curve() - gamma * witness.blinding_eval;
+        verify_opening(
+            &self.prepared,
+            self.parameters.g1_powers()[0],
+            (x, y),
+            &commitment.to_affine(),
+            &witness.w,
+        )
     }
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
-    use crate::setup;
+    use crate::{setup, setup_asymmetric};
     use rand::{rngs::SmallRng, Rng, SeedableRng};
 
     const RNG_SEED: [u8; 32] = [69; 32];
@@ -199,7 +932,7 @@ mod tests {
         params: &'params KZGParams,
     ) -> (KZGProver<'params>, KZGVerifier<'params>) {
         let prover = KZGProver::new(params);
-        let verifier = KZGVerifier::new(params);
+        let verifier = KZGVerifier::new(params).unwrap();
 
         (prover, verifier)
     }
@@ -209,8 +942,8 @@ mod tests {
         let num_coeffs = rng.gen_range(min_coeffs..max_coeffs);
         let mut coeffs = vec![Scalar::zero(); max_coeffs];
 
-        for i in 0..num_coeffs {
-            coeffs[i] = rng.gen::<u64>().into();
+        for c in coeffs.iter_mut().take(num_coeffs) {
+            *c = rng.gen::<u64>().into();
         }
 
         let mut poly = Polynomial::new_from_coeffs(coeffs, num_coeffs - 1);
@@ -224,7 +957,7 @@ mod tests {
         polynomial: &Polynomial,
     ) {
         assert!(
-            verifier.verify_poly(&commitment, &polynomial),
+            verifier.verify_poly(commitment, polynomial),
             "verify_poly failed for commitment {:#?} and polynomial {:#?}",
             commitment,
             polynomial
@@ -237,7 +970,7 @@ mod tests {
         polynomial: &Polynomial,
     ) {
         assert!(
-            !verifier.verify_poly(&commitment, &polynomial),
+            !verifier.verify_poly(commitment, polynomial),
             "expected verify_poly to fail for commitment {:#?} and polynomial {:#?} but it didn't",
             commitment,
             polynomial
@@ -251,7 +984,7 @@ mod tests {
         witness: &KZGWitness,
     ) {
         assert!(
-            verifier.verify_eval(point, &commitment, &witness),
+            verifier.verify_eval(point, commitment, witness, None),
             "verify_eval failed for point {:#?}, commitment {:#?}, and witness {:#?}",
             point,
             commitment,
@@ -265,7 +998,7 @@ mod tests {
         commitment: &KZGCommitment,
         witness: &KZGWitness,
     ) {
-        assert!(!verifier.verify_eval(point, &commitment, &witness), "expected verify_eval to fail for for point {:#?}, commitment {:#?}, and witness {:#?}, but it didn't", point, commitment, witness);
+        assert!(!verifier.verify_eval(point, commitment, witness, None), "expected verify_eval to fail for for point {:#?}, commitment {:#?}, and witness {:#?}, but it didn't", point, commitment, witness);
     }
 
     #[test]
@@ -276,10 +1009,17 @@ mod tests {
         let (prover, verifier) = test_participants(&params);
 
         let polynomial = random_polynomial(&mut rng, 2, 12);
-        let commitment = prover.commit(&polynomial);
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
 
         assert_verify_poly(&verifier, &commitment, &polynomial);
         assert_verify_poly_fails(&verifier, &commitment, &random_polynomial(&mut rng, 2, 12));
+
+        let mut no_g2 = params.clone();
+        no_g2.hs.truncate(1);
+        assert!(matches!(
+            KZGVerifier::new(&no_g2),
+            Err(KZGError::NotEnoughG2Powers { needed: 2, available: 1 })
+        ));
     }
 
     fn random_field_elem_neq(val: Scalar) -> Scalar {
@@ -300,7 +1040,7 @@ mod tests {
         let (prover, verifier) = test_participants(&params);
 
         let polynomial = random_polynomial(&mut rng, 3, 8);
-        let commitment = prover.commit(&polynomial);
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
 
         let mut modified_polynomial = polynomial.clone();
         let new_coeff = random_field_elem_neq(modified_polynomial.coeffs[2]);
@@ -318,12 +1058,12 @@ mod tests {
         let (prover, verifier) = test_participants(&params);
 
         let polynomial = random_polynomial(&mut rng, 5, 13);
-        let commitment = prover.commit(&polynomial);
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
 
         let x: Scalar = rng.gen::<u64>().into();
         let y = polynomial.eval(x);
 
-        let witness = prover.create_witness(&polynomial, (x, y)).unwrap();
+        let witness = prover.create_witness(&polynomial, (x, y), None).unwrap().0;
         assert_verify_eval(&verifier, (x, y), &commitment, &witness);
 
         let y_prime = random_field_elem_neq(y);
@@ -335,8 +1075,8 @@ mod tests {
         coeffs[1] = 1.into();
         let polynomial = Polynomial::new(coeffs);
 
-        let commitment = prover.commit(&polynomial);
-        let witness = prover.create_witness(&polynomial, (1.into(), 4.into())).unwrap();
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
+        let witness = prover.create_witness(&polynomial, (1.into(), 4.into()), None).unwrap().0;
         assert_verify_eval(&verifier, (1.into(), 4.into()), &commitment, &witness);
         assert_verify_eval_fails(&verifier, (1.into(), 5.into()), &commitment, &witness);
     }
@@ -348,7 +1088,7 @@ mod tests {
 
         let (prover, verifier) = test_participants(&params);
         let polynomial = random_polynomial(&mut rng, 8, 15);
-        let commitment = prover.commit(&polynomial);
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
 
         let mut xs: Vec<Scalar> = Vec::with_capacity(8);
         let mut ys: Vec<Scalar> = Vec::with_capacity(8);
@@ -361,7 +1101,7 @@ mod tests {
         let witness = prover
             .create_witness_batched(&polynomial, xs.as_slice(), ys.as_slice())
             .unwrap();
-        assert!(verifier.verify_eval_batched(xs.as_slice(), &commitment, &witness));
+        assert!(verifier.verify_eval_batched(xs.as_slice(), &commitment, &witness).unwrap());
 
         let mut xs: Vec<Scalar> = Vec::with_capacity(8);
         let mut ys: Vec<Scalar> = Vec::with_capacity(8);
@@ -371,7 +1111,42 @@ mod tests {
             ys.push(polynomial.eval(x));
         }
 
-        assert!(!verifier.verify_eval_batched(&xs, &commitment, &witness))
+        assert!(!verifier.verify_eval_batched(&xs, &commitment, &witness).unwrap());
+
+        assert!(!verifier.verify_eval_batched(&[], &commitment, &witness).unwrap());
+        assert!(matches!(
+            prover.create_witness_batched(&polynomial, &[], &[]),
+            Err(KZGError::NoPolynomial)
+        ));
+        assert!(matches!(
+            prover.create_compact_witness_batched(&polynomial, &[], &[]),
+            Err(KZGError::NoPolynomial)
+        ));
+    }
+
+    #[test]
+    fn test_eval_batched_compact() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup::<15>(&mut rng);
+
+        let (prover, verifier) = test_participants(&params);
+        let polynomial = random_polynomial(&mut rng, 8, 15);
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
+
+        for &n in [1, 6].iter() {
+            let xs: Vec<Scalar> = (0..n).map(|_| rng.gen::<u64>().into()).collect();
+            let mut ys: Vec<Scalar> = xs.iter().map(|&x| polynomial.eval(x)).collect();
+
+            let witness = prover.create_compact_witness_batched(&polynomial, &xs, &ys).unwrap();
+            let full = prover.create_witness_batched(&polynomial, &xs, &ys).unwrap();
+            assert_eq!(witness, full.clone().into());
+
+            assert!(verifier.verify_eval_batched_compact(&xs, &ys, &commitment, &witness).unwrap());
+            assert!(verifier.verify_eval_batched(&xs, &commitment, &full).unwrap());
+
+            ys[n - 1] += Scalar::one();
+            assert!(!verifier.verify_eval_batched_compact(&xs, &ys, &commitment, &witness).unwrap());
+        }
     }
 
     #[test]
@@ -381,7 +1156,7 @@ mod tests {
 
         let (prover, verifier) = test_participants(&params);
         let polynomial = random_polynomial(&mut rng, 13, 14);
-        let commitment = prover.commit(&polynomial);
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
 
         let mut xs: Vec<Scalar> = Vec::with_capacity(polynomial.num_coeffs());
         let mut ys: Vec<Scalar> = Vec::with_capacity(polynomial.num_coeffs());
@@ -394,6 +1169,202 @@ mod tests {
         let witness = prover
             .create_witness_batched(&polynomial, xs.as_slice(), ys.as_slice())
             .unwrap();
-        assert!(verifier.verify_eval_batched(xs.as_slice(), &commitment, &witness));
+        assert!(verifier.verify_eval_batched(xs.as_slice(), &commitment, &witness).unwrap());
+    }
+
+    #[test]
+    fn test_asymmetric_params() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup_asymmetric(s, 16, 2).unwrap();
+        assert_eq!(params.gs, setup(s, 16).gs);
+
+        let (prover, verifier) = test_participants(&params);
+        let polynomial = random_polynomial(&mut rng, 8, 16);
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
+
+        let x: Scalar = rng.gen::<u64>().into();
+        let y = polynomial.eval(x);
+        let witness = prover.create_witness(&polynomial, (x, y), None).unwrap().0;
+        assert_verify_eval(&verifier, (x, y), &commitment, &witness);
+
+        let xs: Vec<Scalar> = (0..4).map(|_| rng.gen::<u64>().into()).collect();
+        let ys: Vec<Scalar> = xs.iter().map(|&x| polynomial.eval(x)).collect();
+        let witness = prover.create_witness_batched(&polynomial, &xs, &ys).unwrap();
+        assert!(matches!(
+            verifier.verify_eval_batched(&xs, &commitment, &witness),
+            Err(KZGError::NotEnoughG2Powers { needed: 5, available: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_eval_many() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup::<16>(&mut rng);
+
+        let (prover, verifier) = test_participants(&params);
+        let mut openings: Vec<((Scalar, Scalar), KZGCommitment, KZGWitness)> = (0..10)
+            .map(|_| {
+                let f = random_polynomial(&mut rng, 2, 16);
+                let x: Scalar = rng.gen::<u64>().into();
+                let y = f.eval(x);
+                ((x, y), prover.commit(&f, None).unwrap().0, prover.create_witness(&f, (x, y), None).unwrap().0)
+            })
+            .collect();
+        assert!(verifier.verify_eval_many(&openings, &mut rng));
+
+        (openings[7].0).1 += Scalar::one();
+        assert!(!verifier.verify_eval_many(&openings, &mut rng));
+    }
+
+    #[test]
+    fn test_eval_multi() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup::<16>(&mut rng);
+
+        let (prover, verifier) = test_participants(&params);
+        let polynomials: Vec<(Polynomial, KZGCommitment)> = (0..5)
+            .map(|_| {
+                let f = random_polynomial(&mut rng, 2, 16);
+                let c = prover.commit(&f, None).unwrap().0;
+                (f, c)
+            })
+            .collect();
+
+        let x: Scalar = rng.gen::<u64>().into();
+        let (ys, witness) = prover.create_witness_multi(&polynomials, x, &mut Transcript::new(b"test")).unwrap();
+        for ((f, _), y) in polynomials.iter().zip(ys.iter()) {
+            assert_eq!(f.eval(x), *y);
+        }
+
+        let mut openings: Vec<(KZGCommitment, Scalar)> = polynomials.iter().map(|(_, c)| *c).zip(ys).collect();
+        assert!(verifier.verify_eval_multi(x, &openings, &witness, &mut Transcript::new(b"test")));
+        // a verifier with a different transcript derives a different challenge
+        assert!(!verifier.verify_eval_multi(x, &openings, &witness, &mut Transcript::new(b"other")));
+
+        openings[3].1 += Scalar::one();
+        assert!(!verifier.verify_eval_multi(x, &openings, &witness, &mut Transcript::new(b"test")));
+    }
+
+    #[test]
+    fn test_eval_multi_point() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = setup_asymmetric(rng.gen::<u64>().into(), 16, 2).unwrap();
+
+        let (prover, verifier) = test_participants(&params);
+        let shared: Scalar = rng.gen::<u64>().into();
+        let openings: Vec<(Polynomial, KZGCommitment, Vec<Scalar>)> = (1..5)
+            .map(|num_points| {
+                let f = random_polynomial(&mut rng, 6, 16);
+                let c = prover.commit(&f, None).unwrap().0;
+                // every polynomial is opened at `shared`, plus a few points of its own
+                let mut xs = vec![shared];
+                xs.extend((1..num_points).map(|_| -> Scalar { rng.gen::<u64>().into() }));
+                (f, c, xs)
+            })
+            .collect();
+
+        let (ys, witness) = prover.create_witness_multi_point(&openings, &mut Transcript::new(b"test")).unwrap();
+
+        let mut claims: Vec<(KZGCommitment, Vec<Scalar>, Vec<Scalar>)> = openings
+            .iter()
+            .zip(ys)
+            .map(|((f, c, xs), ys)| {
+                assert_eq!(ys, xs.iter().map(|&x| f.eval(x)).collect::<Vec<_>>());
+                (*c, xs.clone(), ys)
+            })
+            .collect();
+        assert!(verifier.verify_eval_multi_point(&claims, &witness, &mut Transcript::new(b"test")));
+
+        claims[2].2[1] += Scalar::one();
+        assert!(!verifier.verify_eval_multi_point(&claims, &witness, &mut Transcript::new(b"test")));
+    }
+
+    #[test]
+    fn test_degree_bound() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup::<16>(&mut rng);
+
+        // the verifier only needs h and h^alpha
+        let mut verifier_params = params.clone();
+        verifier_params.hs.truncate(2);
+
+        let prover = KZGProver::new(&params);
+        let verifier = KZGVerifier::new(&verifier_params).unwrap();
+        let mut coeffs = vec![Scalar::zero(); 16];
+        for c in coeffs.iter_mut().take(6) {
+            *c = rng.gen::<u64>().into();
+        }
+        let polynomial = Polynomial::new(coeffs);
+        assert_eq!(polynomial.degree(), 5);
+
+        let x: Scalar = rng.gen::<u64>().into();
+        let y = polynomial.eval(x);
+
+        let (commitment, shifted) = prover.commit(&polynomial, Some(5)).unwrap();
+        let (witness, shifted_witness) = prover.create_witness(&polynomial, (x, y), Some(5)).unwrap();
+        let (shifted, shifted_witness) = (shifted.unwrap(), shifted_witness.unwrap());
+        assert_eq!(commitment, prover.commit(&polynomial, None).unwrap().0);
+        assert!(verifier.verify_eval((x, y), &commitment, &witness, Some((5, &shifted, &shifted_witness))));
+        assert!(!verifier.verify_eval((x, y + Scalar::one()), &commitment, &witness, Some((5, &shifted, &shifted_witness))));
+
+        // the prover refuses to commit with a bound that's too small
+        assert!(matches!(prover.commit(&polynomial, Some(4)), Err(KZGError::DegreeBoundExceeded)));
+        assert!(matches!(
+            prover.create_witness(&polynomial, (x, y), Some(4)),
+            Err(KZGError::DegreeBoundExceeded)
+        ));
+
+        // a shifted commitment for a looser bound doesn't pass for a tighter one
+        let (_, loose) = prover.commit(&polynomial, Some(7)).unwrap();
+        let (_, loose_witness) = prover.create_witness(&polynomial, (x, y), Some(7)).unwrap();
+        let (loose, loose_witness) = (loose.unwrap(), loose_witness.unwrap());
+        assert!(!verifier.verify_eval((x, y), &commitment, &witness, Some((4, &loose, &loose_witness))));
+        assert!(!verifier.verify_eval((x, y), &commitment, &witness, Some((16, &loose, &loose_witness))));
+
+        let (commitment, shifted) = prover.commit(&polynomial, None).unwrap();
+        assert!(shifted.is_none());
+        assert!(verifier.verify_eval((x, y), &commitment, &witness, None));
+    }
+
+    #[test]
+    fn test_hiding_eval() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup(s, 12);
+        let hiding = KZGHidingParams::setup(s, 12);
+
+        let prover = KZGHidingProver::new(&params, &hiding);
+        let verifier = KZGHidingVerifier::new(&params, &hiding).unwrap();
+
+        let polynomial = random_polynomial(&mut rng, 4, 12);
+        let blinding = KZGHidingProver::<KZGParams>::random_blinding(polynomial.num_coeffs(), &mut rng);
+        let commitment = prover.commit(&polynomial, &blinding).unwrap();
+        assert!(verifier.verify_poly(&commitment, &polynomial, &blinding));
+
+        // the same polynomial with a different blinding polynomial commits to a different point
+        let other_blinding = KZGHidingProver::<KZGParams>::random_blinding(polynomial.num_coeffs(), &mut rng);
+        assert_ne!(prover.commit(&polynomial, &other_blinding).unwrap(), commitment);
+        assert!(!verifier.verify_poly(&commitment, &polynomial, &other_blinding));
+
+        let x: Scalar = rng.gen::<u64>().into();
+        let y = polynomial.eval(x);
+        let witness = prover.create_witness(&polynomial, &blinding, (x, y)).unwrap();
+        assert!(verifier.verify_eval((x, y), &commitment, &witness));
+
+        let y_prime = random_field_elem_neq(y);
+        assert!(!verifier.verify_eval((x, y_prime), &commitment, &witness));
+        assert!(prover.create_witness(&polynomial, &blinding, (x, y_prime)).is_err());
+
+        let bad_witness = KZGHidingWitness::new(witness.elem(), witness.blinding_eval() + Scalar::one());
+        assert!(!verifier.verify_eval((x, y), &commitment, &bad_witness));
+
+        // a blinding polynomial with more coefficients than there are hiding powers
+        let too_long = KZGHidingProver::<KZGParams>::random_blinding(13, &mut rng);
+        assert!(matches!(
+            prover.commit(&polynomial, &too_long),
+            Err(KZGError::PolynomialDegreeTooLarge)
+        ));
+        assert!(!verifier.verify_poly(&commitment, &polynomial, &too_long));
     }
 }
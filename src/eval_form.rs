@@ -1,14 +1,17 @@
-use blstrs::{pairing, G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
-use pairing::group::ff::PrimeField;
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Scalar};
 use pairing::group::{ff::Field, Group, prime::PrimeCurveAffine, Curve};
+use rand_core::RngCore;
 use std::fmt::Debug;
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
-use crate::ft::EvaluationDomain;
-use crate::polynomial::Polynomial;
-use crate::{KZGCommitment, KZGError, KZGParams, KZGWitness};
+use crate::coeff_form::{interpolate, verify_opening, verify_openings_batched};
+use crate::fk20::FK20Table;
+use crate::ft::{ifft_in_place, EvaluationDomain};
+use crate::lagrange::LagrangeBasis;
+use crate::polynomial::{Polynomial, SubProductTree};
+use crate::{pairings_equal, KZGCommitment, KZGError, KZGParams, KZGWitness, PreparedG2};
 
 // A witness for a several elements - "w_B" in the paper. It's a single group element plus a polynomial
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -36,12 +39,109 @@ impl KZGBatchWitnessEvalForm {
     }
 }
 
+/// a subvector witness from `KZGProverEvalForm::aggregate_witnesses`: the commitment `w` to the
+/// quotient by the vanishing polynomial `A_I` of the indices, and `h^A_I(tau)`, which the verifier
+/// checks against its own `g^A_I(tau)` so that it doesn't need G2 powers up to `|I|`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct KZGSubvectorWitness {
+    w: G1Affine,
+    ha: G2Affine,
+}
+
+impl KZGSubvectorWitness {
+    pub fn elem(&self) -> G1Affine {
+        self.w
+    }
+
+    pub fn vanishing(&self) -> G2Affine {
+        self.ha
+    }
+
+    pub fn new(w: G1Affine, ha: G2Affine) -> Self {
+        KZGSubvectorWitness { w, ha }
+    }
+}
+
+/// a Lagrange basis together with the domain it was computed for
+#[derive(Debug, Clone, Copy)]
+struct DomainBasis<'params> {
+    d: usize,
+    omega: Scalar,
+    shift: Scalar,
+    gs: &'params [G1Projective],
+    hs: &'params [G2Projective],
+}
+
+impl<'params> DomainBasis<'params> {
+    fn from_basis(basis: &'params LagrangeBasis) -> Self {
+        DomainBasis {
+            d: basis.domain_size(),
+            omega: basis.omega(),
+            shift: basis.shift(),
+            gs: basis.g1_basis(),
+            hs: basis.g2_basis(),
+        }
+    }
+
+    /// `shift * omega^i` for every `i` in `indices`
+    fn points(&self, indices: &[usize]) -> Vec<Scalar> {
+        indices
+            .iter()
+            .map(|&i| self.shift * self.omega.pow_vartime([i as u64]))
+            .collect()
+    }
+}
+
+/// errors unless `indices` is a nonempty set of distinct indices below `d`
+fn check_indices(indices: &[usize], d: usize) -> Result<(), KZGError> {
+    if indices.is_empty() {
+        return Err(KZGError::NoPolynomial);
+    }
+
+    let mut seen = vec![false; d];
+    for &i in indices {
+        if i >= d || seen[i] {
+            return Err(KZGError::InvalidIndex(i));
+        }
+        seen[i] = true;
+    }
+
+    Ok(())
+}
+
+/// evaluates `polynomial`, which must have at most `basis.d` coefficients, at every point of the
+/// domain of `basis`
+fn evals_over(basis: &DomainBasis, polynomial: &Polynomial) -> Vec<Scalar> {
+    let mut coeffs = polynomial.slice_coeffs().to_vec();
+    coeffs.resize(basis.d, Scalar::zero());
+
+    let mut evals = EvaluationDomain::new(coeffs, basis.d, basis.d.trailing_zeros(), basis.omega);
+    if basis.shift != Scalar::one() {
+        evals.distribute_powers(basis.shift);
+    }
+    evals.fft();
+
+    evals.into_coeffs()
+}
+
+/// returns the basis for the domain of size `d` shifted by `shift`
+fn find_basis<'a, 'params>(
+    bases: &'a [DomainBasis<'params>],
+    d: usize,
+    shift: Scalar,
+) -> Result<&'a DomainBasis<'params>, KZGError> {
+    bases
+        .iter()
+        .find(|b| b.d == d && b.shift == shift)
+        .ok_or(KZGError::UnsupportedDomain(d))
+}
+
 #[derive(Debug, Clone)]
 pub struct KZGProverEvalForm<'params> {
     parameters: &'params KZGParams,
-    lagrange_basis_g: &'params [G1Projective],
+    bases: Vec<DomainBasis<'params>>,
     d: usize,
-    exp: u32,
     omega: Scalar,
 }
 
@@ -51,20 +151,20 @@ pub struct KZGVerifierEvalForm<'params> {
     exp: u32,
     omega: Scalar,
     parameters: &'params KZGParams,
-    lagrange_basis_g: &'params [G1Projective],
-    lagrange_basis_h: &'params [G2Projective],
+    bases: Vec<DomainBasis<'params>>,
+    prepared: PreparedG2,
 }
 
 fn div_by_omega_i(evals: &EvaluationDomain, m: usize) -> EvaluationDomain {
     let mut coeffs = Vec::with_capacity(evals.d);
-    let omega_m = evals.omega.pow_vartime(&[m as u64]);
+    let omega_m = evals.omega.pow_vartime([m as u64]);
     for (j, &f) in evals.coeffs.iter().enumerate() {
         if j == m {
             let mut qm = Scalar::zero();
             let am = Scalar::from(evals.d as u64) * omega_m.invert().unwrap();
 
             for i in (0..evals.d).filter(|&i| i != m) {
-                let omega_i = evals.omega.pow_vartime(&[i as u64]);
+                let omega_i = evals.omega.pow_vartime([i as u64]);
                 let ai = Scalar::from(evals.d as u64) * omega_i.invert().unwrap();
 
                 let mut term = evals.coeffs[i];
@@ -75,7 +175,7 @@ fn div_by_omega_i(evals: &EvaluationDomain, m: usize) -> EvaluationDomain {
 
             coeffs.push(qm);
         } else {
-            let omega_j = evals.omega.pow_vartime(&[j as u64]);
+            let omega_j = evals.omega.pow_vartime([j as u64]);
             coeffs.push(f * (omega_j - omega_m).invert().unwrap())
         }
     }
@@ -89,16 +189,40 @@ impl<'params> KZGProverEvalForm<'params> {
         parameters: &'params KZGParams,
         lagrange_basis_g: &'params [G1Projective],
     ) -> Self {
-        let (d, exp, omega) = EvaluationDomain::compute_omega(parameters.gs.len()).unwrap();
+        let (d, _, omega) = EvaluationDomain::compute_omega(parameters.gs.len()).unwrap();
+        let basis = DomainBasis {
+            d,
+            omega,
+            shift: Scalar::one(),
+            gs: lagrange_basis_g,
+            hs: &[],
+        };
+
         Self {
             parameters,
-            lagrange_basis_g,
+            bases: vec![basis],
             d,
-            exp,
             omega,
         }
     }
 
+    /// like `new`, but uses the domain and G1 points of an owned `LagrangeBasis`
+    pub fn from_basis(parameters: &'params KZGParams, basis: &'params LagrangeBasis) -> Self {
+        Self {
+            parameters,
+            bases: vec![DomainBasis::from_basis(basis)],
+            d: basis.domain_size(),
+            omega: basis.omega(),
+        }
+    }
+
+    /// makes the prover accept evaluations over the domain of `basis`, in addition to the ones it
+    /// already accepts. Which basis is used is picked from the size and shift of the
+    /// `EvaluationDomain` passed in.
+    pub fn add_basis(&mut self, basis: &'params LagrangeBasis) {
+        self.bases.push(DomainBasis::from_basis(basis));
+    }
+
     pub fn parameters(&self) -> &'params KZGParams {
         self.parameters
     }
@@ -111,32 +235,183 @@ impl<'params> KZGProverEvalForm<'params> {
         self.omega
     }
 
-    pub fn commit(&self, evals: &EvaluationDomain) -> KZGCommitment {
-        assert!(self.d == evals.d);
+    /// commits to `evals` using the basis for its domain. Returns `KZGError::UnsupportedDomain` if
+    /// the prover has no basis for it.
+    pub fn commit(&self, evals: &EvaluationDomain) -> Result<KZGCommitment, KZGError> {
+        let basis = find_basis(&self.bases, evals.d, evals.shift)?;
+        if basis.gs.len() < evals.len() {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
 
-        let gs = &self.lagrange_basis_g[..evals.len()];
-        let commitment = G1Projective::multi_exp(gs, evals.as_ref());
+        let gs = &basis.gs[..evals.len()];
+        Ok(G1Projective::multi_exp(gs, evals.as_ref()).to_affine())
+    }
 
-        let commitment = commitment.to_affine();
-        commitment
+    /// returns the commitment after adding `delta` to entry `i` of the committed evaluations over
+    /// the first domain the prover was created with, without recommitting - this is
+    /// `commitment + delta * g^L_i(tau)`. Returns `KZGError::InvalidIndex` if `i` is outside the domain.
+    pub fn update_commitment(&self, commitment: &KZGCommitment, i: usize, delta: Scalar) -> Result<KZGCommitment, KZGError> {
+        let basis = &self.bases[0];
+        match basis.gs.get(i) {
+            Some(g) if i < basis.d => Ok((commitment.to_curve() + g * delta).to_affine()),
+            _ => Err(KZGError::InvalidIndex(i)),
+        }
     }
 
-    pub fn create_witness(&self, evals: &EvaluationDomain, i: usize) -> KZGWitness {
+    /// returns the witness for the `i`th entry of `evals`. Returns `KZGError::UnsupportedDomain` if
+    /// the prover has no basis for the domain of `evals`.
+    pub fn create_witness(&self, evals: &EvaluationDomain, i: usize) -> Result<KZGWitness, KZGError> {
+        let basis = find_basis(&self.bases, evals.d, evals.shift)?;
+        if i >= evals.coeffs.len() {
+            return Err(KZGError::InvalidIndex(i));
+        }
+        if basis.gs.len() < evals.coeffs.len() {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
+
         let y = evals.coeffs[i];
         let numerator = EvaluationDomain {
             coeffs: evals.coeffs.iter().map(|c| c - y).collect(),
             ..*evals
         };
 
-        let q = div_by_omega_i(&numerator, i);
+        let mut q = div_by_omega_i(&numerator, i);
+
+        // over the coset every difference of points picks up a factor of `shift`
+        if evals.shift != Scalar::one() {
+            let shift_inv = evals.shift.invert().unwrap();
+            for c in q.coeffs.iter_mut() {
+                *c *= shift_inv;
+            }
+        }
+
         let w = if q.coeffs.len() == 1 {
-            self.lagrange_basis_g[0] * q.coeffs[0]
+            basis.gs[0] * q.coeffs[0]
         } else {
-            let gs = &self.lagrange_basis_g[..q.len()];
+            let gs = &basis.gs[..q.len()];
             G1Projective::multi_exp(gs, q.as_ref())
         };
 
-        w.to_affine()
+        Ok(w.to_affine())
+    }
+
+    /// returns the witness for every index of `evals`, i.e. what `create_witness` returns for each
+    /// `i < evals.d`, in O(d log d) group operations using FK20 (see `fk20`)
+    pub fn create_all_witnesses(&self, evals: &EvaluationDomain) -> Result<Vec<KZGWitness>, KZGError> {
+        self.create_cell_witnesses(evals, 1)
+    }
+
+    /// splits the domain of `evals` into `evals.d / l` cells of size `l` and returns a witness for
+    /// every cell, in O(d log d) group operations using FK20 (see `fk20`). Cell `i` is the coset
+    /// `shift * omega^i * mu_l`, i.e. the indices `i, i + d / l, i + 2 * d / l, ...`. The witnesses
+    /// are checked with `KZGVerifierEvalForm::verify_cell`. `l` must be a power of two no larger
+    /// than `evals.d`, or this returns `UnsupportedDomain(l)`.
+    pub fn create_cell_witnesses(&self, evals: &EvaluationDomain, l: usize) -> Result<Vec<KZGWitness>, KZGError> {
+        let shift = evals.shift;
+        let mut coeffs = evals.clone();
+        coeffs.ifft();
+        if shift != Scalar::one() {
+            coeffs.distribute_powers(shift.invert().unwrap());
+        }
+        let polynomial: Polynomial = coeffs.into();
+
+        FK20Table::with_coset_size(self.parameters, evals.d, l)?.open_all(&polynomial, shift)
+    }
+
+    /// aggregates witnesses from `create_witness` for distinct indices `I` of the first domain the
+    /// prover was created with into a single witness for the subvector at `I`, without needing the
+    /// evaluations themselves. With `A_I(X) = prod_{i in I} (X - x_i)`, partial fractions give
+    /// `1 / A_I(X) = sum_i c_i / (X - x_i)` where `c_i = 1 / A_I'(x_i)`, so the quotient
+    /// `(f - r_I) / A_I` is `sum_i c_i (f - f(x_i)) / (X - x_i)` and its witness is `sum_i c_i w_i`.
+    /// The witness also carries `h^A_I(tau)`, so the parameters need `|I| + 1` G2 powers unless `I`
+    /// is the whole domain, where `A_I` is zero over the domain. Takes O(|I|^2) field operations.
+    /// The result is checked with `KZGVerifierEvalForm::verify_subvector`.
+    pub fn aggregate_witnesses(&self, witnesses: &[(usize, KZGWitness)]) -> Result<KZGSubvectorWitness, KZGError> {
+        let basis = &self.bases[0];
+        let indices: Vec<usize> = witnesses.iter().map(|(i, _)| *i).collect();
+        check_indices(&indices, basis.d)?;
+
+        let xs = basis.points(&indices);
+        let ha = if indices.len() == basis.d {
+            G2Projective::identity()
+        } else {
+            self.parameters.check_g2_powers(indices.len() + 1)?;
+            let a = SubProductTree::new_from_points(&xs).product;
+            G2Projective::multi_exp(&self.parameters.hs[..a.num_coeffs()], a.slice_coeffs())
+        };
+
+        let cs: Vec<Scalar> = xs
+            .iter()
+            .enumerate()
+            .map(|(i, x_i)| {
+                xs.iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .fold(Scalar::one(), |acc, (_, x_j)| acc * (x_i - x_j))
+                    .invert()
+                    .unwrap()
+            })
+            .collect();
+
+        let ws: Vec<G1Projective> = witnesses.iter().map(|(_, w)| w.to_curve()).collect();
+        let w = G1Projective::multi_exp(&ws, &cs).to_affine();
+        Ok(KZGSubvectorWitness::new(w, ha.to_affine()))
+    }
+
+    /// creates a single witness for the evaluations at `indices`, which must be distinct. The
+    /// witness holds the evaluations of the polynomial `r` interpolating `evals` at `indices`, and
+    /// a commitment to `q = (f - r) / A_I`, where `A_I` vanishes at the chosen points. `q` is
+    /// computed in evaluation form: outside of `I` it's a division, and at `x_k` in `I` it's
+    /// `(f - r)'(x_k) / A_I'(x_k)`. If `indices` covers the whole domain, `q` is zero.
+    pub fn create_witness_batched(
+        &self,
+        evals: &EvaluationDomain,
+        indices: &[usize],
+    ) -> Result<KZGBatchWitnessEvalForm, KZGError> {
+        let basis = find_basis(&self.bases, evals.d, evals.shift)?;
+        let d = basis.d;
+        check_indices(indices, d)?;
+        if indices.len() == d {
+            return Ok(KZGBatchWitnessEvalForm::new(evals.clone(), G1Affine::identity()));
+        }
+
+        let xs = basis.points(indices);
+        let ys: Vec<Scalar> = indices.iter().map(|&i| evals.coeffs[i]).collect();
+        let tree = SubProductTree::new_from_points(&xs);
+        let r = evals_over(basis, &interpolate(&xs, &ys, &tree));
+        let a = evals_over(basis, &tree.product);
+
+        let points = basis.points(&(0..d).collect::<Vec<_>>());
+        let mut in_subset = vec![false; d];
+        for &i in indices {
+            in_subset[i] = true;
+        }
+
+        // g = f - r vanishes on I
+        let g: Vec<Scalar> = evals.coeffs.iter().zip(r.iter()).map(|(f, r)| f - r).collect();
+        let mut q = vec![Scalar::zero(); d];
+        for k in (0..d).filter(|&k| !in_subset[k]) {
+            q[k] = g[k] * a[k].invert().unwrap();
+        }
+
+        // over the domain L_j'(x_k) = x_j / (x_k (x_k - x_j)) for j != k, and g_j = 0 for j in I
+        for (&k, x_k) in indices.iter().zip(xs.iter()) {
+            let g_prime = (0..d)
+                .filter(|&j| !in_subset[j])
+                .fold(Scalar::zero(), |acc, j| {
+                    acc + g[j] * points[j] * (x_k - points[j]).invert().unwrap()
+                })
+                * x_k.invert().unwrap();
+            let a_prime = xs
+                .iter()
+                .filter(|&x_i| x_i != x_k)
+                .fold(Scalar::one(), |acc, x_i| acc * (x_k - x_i));
+
+            q[k] = g_prime * a_prime.invert().unwrap();
+        }
+
+        let w = G1Projective::multi_exp(&basis.gs[..d], &q).to_affine();
+        Ok(KZGBatchWitnessEvalForm::new(evals.clone_with_different_coeffs(r), w))
     }
 
     pub fn create_witness_all(&self) -> KZGWitness {
@@ -147,21 +422,55 @@ impl<'params> KZGProverEvalForm<'params> {
 }
 
 impl<'params> KZGVerifierEvalForm<'params> {
-    pub fn new(parameters: &'params KZGParams, lagrange_basis_g: &'params [G1Projective], lagrange_basis_h: &'params [G2Projective]) -> Self {
-        let (d, exp, omega) = EvaluationDomain::compute_omega(parameters.gs.len()).unwrap();
-        KZGVerifierEvalForm {
+    /// needs at least two G2 powers, returns `KZGError::NotEnoughG2Powers` otherwise
+    pub fn new(
+        parameters: &'params KZGParams,
+        lagrange_basis_g: &'params [G1Projective],
+        lagrange_basis_h: &'params [G2Projective],
+    ) -> Result<Self, KZGError> {
+        let (d, exp, omega) = EvaluationDomain::compute_omega(parameters.gs.len())?;
+        let basis = DomainBasis {
+            d,
+            omega,
+            shift: Scalar::one(),
+            gs: lagrange_basis_g,
+            hs: lagrange_basis_h,
+        };
+
+        Ok(KZGVerifierEvalForm {
             parameters,
             d,
             exp,
             omega,
-            lagrange_basis_g,
-            lagrange_basis_h
-        }
+            bases: vec![basis],
+            prepared: PreparedG2::new(&parameters.hs)?,
+        })
+    }
+
+    /// like `new`, but uses the domain and points of an owned `LagrangeBasis`
+    pub fn from_basis(parameters: &'params KZGParams, basis: &'params LagrangeBasis) -> Result<Self, KZGError> {
+        Ok(KZGVerifierEvalForm {
+            parameters,
+            d: basis.domain_size(),
+            exp: basis.domain_size().trailing_zeros(),
+            omega: basis.omega(),
+            bases: vec![DomainBasis::from_basis(basis)],
+            prepared: PreparedG2::new(&parameters.hs)?,
+        })
+    }
+
+    /// see `KZGProverEvalForm::add_basis`
+    pub fn add_basis(&mut self, basis: &'params LagrangeBasis) {
+        self.bases.push(DomainBasis::from_basis(basis));
     }
 
     pub fn verify_poly(&self, commitment: &KZGCommitment, evals: &EvaluationDomain) -> bool {
+        let shift = evals.shift;
         let mut evals = evals.clone();
         evals.ifft();
+        if shift != Scalar::one() {
+            evals.distribute_powers(shift.invert().unwrap());
+        }
         let polynomial: Polynomial = evals.into();
 
         let gs = &self.parameters.gs[..polynomial.num_coeffs()];
@@ -170,24 +479,229 @@ impl<'params> KZGVerifierEvalForm<'params> {
         check.to_affine() == *commitment
     }
 
+    /// verifies an opening at the `i`th point of the first domain the verifier was created with
     pub fn verify_eval(
         &self,
         (i, y): (usize, Scalar),
         commitment: &KZGCommitment,
         witness: &KZGWitness,
     ) -> bool {
-        let omega = Scalar::root_of_unity().pow_vartime(&[1 << (Scalar::S - self.exp)]);
-        let omega_i = omega.pow_vartime(&[i as u64]);
-        let lhs = pairing(
-            witness,
-            &(self.parameters.hs[1] - self.parameters.hs[0] * omega_i).to_affine(),
-        );
-        let rhs = pairing(
-            &(commitment.to_curve() - self.parameters.gs[0] * y).to_affine(),
-            &self.parameters.hs[0].to_affine(),
-        );
+        self.verify_eval_in(&self.bases[0], (i, y), commitment, witness)
+    }
 
-        lhs == rhs
+    /// verifies an opening at the `i`th point of the domain of size `d` shifted by `shift`, i.e.
+    /// at `shift * omega^i`. Returns `KZGError::UnsupportedDomain` if the verifier has no basis for
+    /// that domain.
+    pub fn verify_eval_over(
+        &self,
+        d: usize,
+        shift: Scalar,
+        (i, y): (usize, Scalar),
+        commitment: &KZGCommitment,
+        witness: &KZGWitness,
+    ) -> Result<bool, KZGError> {
+        let basis = find_basis(&self.bases, d, shift)?;
+        Ok(self.verify_eval_in(basis, (i, y), commitment, witness))
+    }
+
+    fn verify_eval_in(
+        &self,
+        basis: &DomainBasis,
+        (i, y): (usize, Scalar),
+        commitment: &KZGCommitment,
+        witness: &KZGWitness,
+    ) -> bool {
+        let x = basis.shift * basis.omega.pow_vartime([i as u64]);
+        verify_opening(&self.prepared, self.parameters.gs[0], (x, y), commitment, witness)
+    }
+
+    /// like `KZGVerifier::verify_eval_many`, for openings at points of the first domain the
+    /// verifier was created with
+    pub fn verify_eval_many<R: RngCore>(
+        &self,
+        openings: &[((usize, Scalar), KZGCommitment, KZGWitness)],
+        rng: &mut R,
+    ) -> bool {
+        let basis = &self.bases[0];
+        verify_openings_batched(
+            self.parameters.gs[0],
+            &self.prepared,
+            openings
+                .iter()
+                .map(|&((i, y), c, w)| (basis.shift * basis.omega.pow_vartime([i as u64]), y, c, w)),
+            rng,
+        )
+    }
+
+    /// verifies a witness from `KZGProverEvalForm::create_cell_witnesses` for cell `i` of size `l`
+    /// of the first domain the verifier was created with. `ys` are the evaluations at the indices
+    /// `i, i + d / l, i + 2 * d / l, ...` of the cell, in that order. With `x = shift * omega^i` and
+    /// `r` the polynomial interpolating `ys` over the coset `x * mu_l`, this checks
+    /// `e(w, h^(tau^l - x^l)) == e(C - g^r(tau), h)`. Needs `l + 1` G2 powers. Returns `false` if `l`
+    /// isn't a power of two dividing the domain size or `i` isn't one of the `d / l` cells.
+    pub fn verify_cell(
+        &self,
+        l: usize,
+        i: usize,
+        ys: &[Scalar],
+        commitment: &KZGCommitment,
+        witness: &KZGWitness,
+    ) -> Result<bool, KZGError> {
+        if !l.is_power_of_two() || l > self.d || i >= self.d / l || ys.len() != l {
+            return Ok(false);
+        }
+        self.parameters.check_g2_powers(l + 1)?;
+
+        let basis = &self.bases[0];
+        let x = basis.shift * basis.omega.pow_vartime([i as u64]);
+
+        // r(x * Y) interpolates ys over mu_l, so its coefficients are the inverse FFT of ys scaled
+        // by x^-k
+        let mut rs = ys.to_vec();
+        ifft_in_place(&mut rs)?;
+        let x_inv = x.invert().unwrap();
+        let mut u = Scalar::one();
+        for r in rs.iter_mut() {
+            *r *= u;
+            u *= x_inv;
+        }
+
+        let gr = if l == 1 {
+            self.parameters.gs[0] * rs[0]
+        } else {
+            G1Projective::multi_exp(&self.parameters.gs[..l], &rs)
+        };
+        let hz = self.parameters.hs[l] - self.parameters.hs[0] * x.pow_vartime([l as u64]);
+
+        let hz: G2Prepared = hz.to_affine().into();
+        Ok(pairings_equal(witness, &hz, &(commitment.to_curve() - gr).to_affine(), &self.prepared.h))
+    }
+
+    /// verifies a subvector witness from `KZGProverEvalForm::aggregate_witnesses`, i.e. that the
+    /// committed evaluations over the first domain the verifier was created with are `values[k]` at
+    /// `indices[k]`. With `r_I` interpolating the values and `A_I` vanishing on the indices,
+    /// `g^r_I(tau)` and `g^A_I(tau)` are committed to with the G1 Lagrange basis. This checks that
+    /// the witness's `h^A_I(tau)` matches, `e(g^A_I(tau), h) == e(g, h^A_I(tau))`, and then
+    /// `e(w, h^A_I(tau)) == e(C - g^r_I(tau), h)`, so only `h` is needed from G2.
+    pub fn verify_subvector(
+        &self,
+        indices: &[usize],
+        values: &[Scalar],
+        commitment: &KZGCommitment,
+        witness: &KZGSubvectorWitness,
+    ) -> Result<bool, KZGError> {
+        let basis = &self.bases[0];
+        if indices.len() != values.len() || check_indices(indices, basis.d).is_err() {
+            return Ok(false);
+        }
+        if basis.gs.len() < basis.d {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
+
+        let xs = basis.points(indices);
+        let tree = SubProductTree::new_from_points(&xs);
+
+        // A vanishes on the whole domain if it has a root at every point, and has too many
+        // coefficients for `evals_over` anyway
+        let ga = if tree.product.degree() == basis.d {
+            G1Projective::identity()
+        } else {
+            G1Projective::multi_exp(&basis.gs[..basis.d], &evals_over(basis, &tree.product))
+        };
+
+        let ha: G2Prepared = witness.ha.into();
+        let g = self.parameters.gs[0].to_affine();
+        if !pairings_equal(&ga.to_affine(), &self.prepared.h, &g, &ha) {
+            return Ok(false);
+        }
+
+        let r = interpolate(&xs, values, &tree);
+        self.check_vanishing_quotient(basis, &evals_over(basis, &r), &ha, commitment, &witness.w)
+    }
+
+    /// verifies a witness from `KZGProverEvalForm::create_witness_batched`, i.e. that the committed
+    /// evaluations over the first domain the verifier was created with are `values[k]` at
+    /// `indices[k]`. Checks that the witness's `r` is over that domain and matches `values` at
+    /// `indices`, and then `e(w, h^A_I(tau)) == e(C - g^r(tau), h)`. Unlike `verify_subvector`,
+    /// this commits to `A_I` itself, so it needs the whole G2 Lagrange basis and returns
+    /// `KZGError::NotEnoughG2Powers` without it.
+    pub fn verify_eval_batched(
+        &self,
+        indices: &[usize],
+        values: &[Scalar],
+        commitment: &KZGCommitment,
+        witness: &KZGBatchWitnessEvalForm,
+    ) -> Result<bool, KZGError> {
+        self.verify_eval_batched_in(&self.bases[0], indices, values, commitment, witness)
+    }
+
+    /// like `verify_eval_batched`, over the domain of size `d` shifted by `shift`. Returns
+    /// `KZGError::UnsupportedDomain` if the verifier has no basis for that domain.
+    pub fn verify_eval_batched_over(
+        &self,
+        d: usize,
+        shift: Scalar,
+        indices: &[usize],
+        values: &[Scalar],
+        commitment: &KZGCommitment,
+        witness: &KZGBatchWitnessEvalForm,
+    ) -> Result<bool, KZGError> {
+        let basis = find_basis(&self.bases, d, shift)?;
+        self.verify_eval_batched_in(basis, indices, values, commitment, witness)
+    }
+
+    fn verify_eval_batched_in(
+        &self,
+        basis: &DomainBasis,
+        indices: &[usize],
+        values: &[Scalar],
+        commitment: &KZGCommitment,
+        witness: &KZGBatchWitnessEvalForm,
+    ) -> Result<bool, KZGError> {
+        // the witness is untrusted, so its domain has to be the one we're checking against
+        let r = &witness.r;
+        if r.d != basis.d || r.shift != basis.shift || r.coeffs.len() != basis.d {
+            return Ok(false);
+        }
+        if indices.len() != values.len() || check_indices(indices, basis.d).is_err() {
+            return Ok(false);
+        }
+        if indices.iter().zip(values.iter()).any(|(&i, y)| r.coeffs[i] != *y) {
+            return Ok(false);
+        }
+
+        if basis.hs.len() < basis.d {
+            return Err(KZGError::NotEnoughG2Powers { needed: basis.d, available: basis.hs.len() });
+        }
+
+        // see `verify_subvector` for the whole domain
+        let tree = SubProductTree::new_from_points(&basis.points(indices));
+        let ha = if tree.product.degree() == basis.d {
+            G2Projective::identity()
+        } else {
+            G2Projective::multi_exp(&basis.hs[..basis.d], &evals_over(basis, &tree.product))
+        };
+
+        let ha: G2Prepared = ha.to_affine().into();
+        self.check_vanishing_quotient(basis, r.as_ref(), &ha, commitment, &witness.w)
+    }
+
+    /// checks `e(w, ha) == e(C - g^r(tau), h)`, where `r` is given by its evaluations over the
+    /// domain of `basis` and committed to with its G1 Lagrange basis
+    fn check_vanishing_quotient(
+        &self,
+        basis: &DomainBasis,
+        r: &[Scalar],
+        ha: &G2Prepared,
+        commitment: &KZGCommitment,
+        witness: &KZGWitness,
+    ) -> Result<bool, KZGError> {
+        if basis.gs.len() < basis.d || r.len() != basis.d {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
+
+        let gr = G1Projective::multi_exp(&basis.gs[..basis.d], r);
+        Ok(pairings_equal(witness, ha, &(commitment.to_curve() - gr).to_affine(), &self.prepared.h))
     }
 
     pub fn verify_eval_all(
@@ -196,93 +710,154 @@ impl<'params> KZGVerifierEvalForm<'params> {
         commitment: &KZGCommitment,
         witness: &KZGWitness,
     ) -> bool {
+        let basis = &self.bases[0];
+
         let mut z = EvaluationDomain::new(vec![Scalar::zero(); self.d], self.d, self.exp, self.omega);
         z.coeffs[0] = -Scalar::one();
         z.coeffs[self.d - 1] = Scalar::one();
 
-        let hs = &self.lagrange_basis_h[..z.len()];
+        let hs = &basis.hs[..z.len()];
         let hz = G2Projective::multi_exp(hs, z.coeffs.as_slice());
 
         let r = EvaluationDomain::new(ys.to_vec(), self.d, self.exp, self.omega);
 
-        let gs = &self.lagrange_basis_g[..r.len()];
+        let gs = &basis.gs[..r.len()];
         let gr = G1Projective::multi_exp(gs, r.coeffs.as_slice());
 
-        let lhs = pairing(witness, &hz.to_affine());
-        let rhs = pairing(
-            &(commitment.to_curve() - gr).to_affine(),
-            &self.parameters.hs[0].to_affine(),
-        );
-
-        lhs == rhs
+        let hz: G2Prepared = hz.to_affine().into();
+        pairings_equal(witness, &hz, &(commitment.to_curve() - gr).to_affine(), &self.prepared.h)
     }
 }
 
-pub fn compute_lagrange_basis_and_polynomials(params: &KZGParams) -> (Vec<G1Projective>, Vec<G2Projective>, Vec<Polynomial>) {
-    let d = params.gs.len();
-    assert!(d & (d - 1) == 0);
-
-    let (d, _, omega) = EvaluationDomain::compute_omega(params.gs.len()).unwrap();
-    let mut gs = Vec::with_capacity(d);
-    let mut hs = Vec::with_capacity(d);
-    let mut ls = Vec::with_capacity(d);
+/// keys for updating eval-form witnesses when an entry changes, without recomputing them
+/// (Tomescu et al., "Aggregatable Subvector Commitments for Stateless Cryptocurrencies").
+///
+/// With `x_i = shift * omega^i` and `A(X) = X^d - shift^d` the vanishing polynomial of the domain,
+/// `a_i = g^(A(tau) / (tau - x_i))` and `u_i = g^((L_i(tau) - 1) / (tau - x_i))`. Both are computed
+/// with an inverse FFT in G1, so building the keys takes O(d log d) group operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateKeys {
+    d: usize,
+    omega: Scalar,
+    shift: Scalar,
+    a: Vec<G1Projective>,
+    u: Vec<G1Projective>,
+}
 
-    for i in 0..d {
-        let xi = omega.pow_vartime(&[i as u64]);
-        let mut l = Polynomial::new_monic_of_degree(0);
-        for j in (0..d).filter(|&j| j != i) {
-            let xj = omega.pow_vartime(&[j as u64]);
-            l = l.best_mul(&Polynomial::new(vec![-xj, Scalar::one()]));
-            l = l.scalar_multiplication((xi - xj).invert().unwrap());
+impl UpdateKeys {
+    /// computes the keys for the coset `shift * H` of the subgroup `H` of size `d`. `d` must be a
+    /// power of two no larger than the number of G1 powers in `params`.
+    pub fn new(params: &KZGParams, d: usize, shift: Scalar) -> Result<Self, KZGError> {
+        if !d.is_power_of_two() || bool::from(shift.is_zero()) {
+            return Err(KZGError::UnsupportedDomain(d));
+        }
+        if d > params.gs.len() {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
+        let (_, _, omega) = EvaluationDomain::compute_omega(d)?;
+
+        // L_i(X) = 1/d * sum_k x_i^-k X^k, so A(X) / (X - x_i) = A'(x_i) L_i(X) = d x_i^(d - 1) L_i(X)
+        // and (L_i(X) - 1) / (X - x_i) = 1/d * sum_k (d - 1 - k) x_i^-(k + 1) X^k. Both sums are
+        // inverse FFTs once the powers of tau are scaled by shift^-k.
+        let shift_inv = shift.invert().unwrap();
+        let mut a = params.gs[..d].to_vec();
+        let mut u = params.gs[..d].to_vec();
+        let mut v = Scalar::one();
+        for (k, (a, u)) in a.iter_mut().zip(u.iter_mut()).enumerate() {
+            *a *= v;
+            *u *= v * Scalar::from((d - 1 - k) as u64);
+            v *= shift_inv;
+        }
+        ifft_in_place(&mut a)?;
+        ifft_in_place(&mut u)?;
+
+        let n = Scalar::from(d as u64);
+        let mut x = shift;
+        for (a, u) in a.iter_mut().zip(u.iter_mut()) {
+            *a *= n * x.pow_vartime([(d - 1) as u64]);
+            *u *= x.invert().unwrap();
+            x *= omega;
         }
 
+        Ok(UpdateKeys { d, omega, shift, a, u })
+    }
 
-        let coeffs = l.coeffs.clone();
-        let g = &params.gs[..coeffs.len()];
-        gs.push(G1Projective::multi_exp(g, coeffs.as_slice()));
-
-        let h = &params.hs[..coeffs.len()];
-        hs.push(G2Projective::multi_exp(h, coeffs.as_slice()));
-
-        ls.push(l);
+    pub fn domain_size(&self) -> usize {
+        self.d
     }
 
-    (gs, hs, ls)
-}
+    pub fn shift(&self) -> Scalar {
+        self.shift
+    }
 
-/// params.gs.len() must be a power of two.
-pub fn compute_lagrange_basis(params: &KZGParams) -> (Vec<G1Projective>, Vec<G2Projective>) {
-    let d = params.gs.len();
-    assert!(d & (d - 1) == 0);
+    /// returns the witness for entry `j` after adding `delta` to entry `i`, given its witness
+    /// before the change. For `i == j` that's `w + delta * u_i`. Otherwise the quotient changes by
+    /// `delta * L_i(X) / (X - x_j)`, which commits to `delta * (a_i - a_j) / (A'(x_i) (x_i - x_j))`.
+    /// Returns `KZGError::InvalidIndex` if `i` or `j` is outside the domain.
+    pub fn update_witness(&self, witness: &KZGWitness, j: usize, i: usize, delta: Scalar) -> Result<KZGWitness, KZGError> {
+        if i >= self.d {
+            return Err(KZGError::InvalidIndex(i));
+        }
+        if j >= self.d {
+            return Err(KZGError::InvalidIndex(j));
+        }
+        if i == j {
+            return Ok((witness.to_curve() + self.u[i] * delta).to_affine());
+        }
 
-    let (d, _, omega) = EvaluationDomain::compute_omega(params.gs.len()).unwrap();
-    let mut gs = Vec::with_capacity(d);
-    let mut hs = Vec::with_capacity(d);
+        let x_i = self.shift * self.omega.pow_vartime([i as u64]);
+        let x_j = self.shift * self.omega.pow_vartime([j as u64]);
+        let a_prime = Scalar::from(self.d as u64) * x_i.pow_vartime([(self.d - 1) as u64]);
+        let coeff = delta * (a_prime * (x_i - x_j)).invert().unwrap();
 
-    for i in 0..d {
-        let xi = omega.pow_vartime(&[i as u64]);
-        let mut l = Polynomial::new_monic_of_degree(0);
-        for j in (0..d).filter(|&j| j != i) {
-            let xj = omega.pow_vartime(&[j as u64]);
-            l = l.best_mul(&Polynomial::new(vec![-xj, Scalar::one()]));
-            l = l.scalar_multiplication((xi - xj).invert().unwrap());
-        }
+        Ok((witness.to_curve() + (self.a[i] - self.a[j]) * coeff).to_affine())
+    }
+}
 
-        let coeffs = l.coeffs();
-        let g = &params.gs[..coeffs.len()];
-        gs.push(G1Projective::multi_exp(g, coeffs.as_slice()));
+/// params.gs.len() must be a power of two, and there must be at least as many G2 powers.
+/// the basis is the inverse FFT of the powers of tau, so this takes O(n log n) group operations.
+pub fn compute_lagrange_basis(params: &KZGParams) -> Result<(Vec<G1Projective>, Vec<G2Projective>), KZGError> {
+    compute_lagrange_basis_for(params, params.gs.len(), Scalar::one())
+}
 
-        let h = &params.hs[..coeffs.len()];
-        hs.push(G2Projective::multi_exp(h, coeffs.as_slice()));
+/// computes the Lagrange basis for the coset `shift * H`, where `H` is the subgroup of size `d`.
+/// `d` must be a power of two no larger than the number of G1 and G2 powers in `params`, and
+/// `shift` must be nonzero.
+pub fn compute_lagrange_basis_for(params: &KZGParams, d: usize, shift: Scalar) -> Result<(Vec<G1Projective>, Vec<G2Projective>), KZGError> {
+    if !d.is_power_of_two() || bool::from(shift.is_zero()) {
+        return Err(KZGError::UnsupportedDomain(d));
+    }
+    if d > params.gs.len() {
+        return Err(KZGError::PolynomialDegreeTooLarge);
     }
+    params.check_g2_powers(d)?;
+
+    // over the coset, L_i(X) = 1/d * sum_j (shift * omega^i)^-j X^j, so the powers of tau get
+    // scaled by shift^-j before the inverse FFT
+    let mut gs = params.gs[..d].to_vec();
+    let mut hs = params.hs[..d].to_vec();
+    if shift != Scalar::one() {
+        let shift_inv = shift.invert().unwrap();
+        let mut u = Scalar::one();
+        for (g, h) in gs.iter_mut().zip(hs.iter_mut()) {
+            *g *= u;
+            *h *= u;
+            u *= shift_inv;
+        }
+    }
+
+    ifft_in_place(&mut gs)?;
+    ifft_in_place(&mut hs)?;
 
-    (gs, hs)
+    Ok((gs, hs))
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
     use crate::{setup, utils::is_power_of_two};
+    use pairing::group::ff::PrimeField;
     use rand::{rngs::SmallRng, Rng, SeedableRng};
 
     const RNG_SEED: [u8; 32] = [69; 32];
@@ -300,17 +875,13 @@ mod tests {
         lagrange_basis_h: &'params [G2Projective],
     ) -> (KZGProverEvalForm<'params>, KZGVerifierEvalForm<'params>) {
         let prover = KZGProverEvalForm::new(params, lagrange_basis_g);
-        let verifier = KZGVerifierEvalForm::new(params, lagrange_basis_g, lagrange_basis_h);
+        let verifier = KZGVerifierEvalForm::new(params, lagrange_basis_g, lagrange_basis_h).unwrap();
 
         (prover, verifier)
     }
 
     fn random_evals(rng: &mut SmallRng, d: usize) -> EvaluationDomain {
-        let mut coeffs = vec![Scalar::zero(); d];
-
-        for i in 0..d {
-            coeffs[i] = rng.gen::<u64>().into();
-        }
+        let coeffs = (0..d).map(|_| rng.gen::<u64>().into()).collect();
 
         EvaluationDomain::from_coeffs(coeffs).unwrap()
     }
@@ -320,7 +891,7 @@ mod tests {
         const N: usize = 10;
         let (d, exp, omega) = EvaluationDomain::compute_omega(N).unwrap();
         let mut rng = SmallRng::from_seed(RNG_SEED);
-        let omega_3 = omega.pow_vartime(&[3 as u64]);
+        let omega_3 = omega.pow_vartime([3]);
 
         let mut top = Polynomial::new(random_evals(&mut rng, d).coeffs);
         let y = top.eval(omega_3);
@@ -363,7 +934,7 @@ mod tests {
         evals: &EvaluationDomain,
     ) {
         assert!(
-            verifier.verify_poly(&commitment, &evals),
+            verifier.verify_poly(commitment, evals),
             "verify_poly failed for commitment {:#?} and polynomial {:#?}",
             commitment,
             evals
@@ -376,7 +947,7 @@ mod tests {
         evals: &EvaluationDomain,
     ) {
         assert!(
-            !verifier.verify_poly(&commitment, &evals),
+            !verifier.verify_poly(commitment, evals),
             "expected verify_poly to fail for commitment {:#?} and polynomial {:#?} but it didn't",
             commitment,
             evals
@@ -387,12 +958,12 @@ mod tests {
     fn test_basic() {
         let mut rng = SmallRng::from_seed(RNG_SEED);
         let params = test_setup(&mut rng, 16);
-        let lagrange_basis = compute_lagrange_basis(&params);
+        let lagrange_basis = compute_lagrange_basis(&params).unwrap();
 
-        let (mut prover, verifier) = test_participants(&params, lagrange_basis.0.as_slice(), lagrange_basis.1.as_slice());
+        let (prover, verifier) = test_participants(&params, lagrange_basis.0.as_slice(), lagrange_basis.1.as_slice());
 
         let evals = random_evals(&mut rng, prover.d);
-        let commitment = prover.commit(&evals);
+        let commitment = prover.commit(&evals).unwrap();
 
         assert_verify_poly(&verifier, &commitment, &evals);
         assert_verify_poly_fails(&verifier, &commitment, &random_evals(&mut rng, prover.d));
@@ -411,12 +982,12 @@ mod tests {
     fn test_modify_single_coeff() {
         let mut rng = SmallRng::from_seed(RNG_SEED);
         let params = test_setup(&mut rng, 8);
-        let lagrange_basis = compute_lagrange_basis(&params);
+        let lagrange_basis = compute_lagrange_basis(&params).unwrap();
 
         let (prover, verifier) = test_participants(&params, lagrange_basis.0.as_slice(), lagrange_basis.1.as_slice());
 
         let evals = random_evals(&mut rng, prover.d);
-        let commitment = prover.commit(&evals);
+        let commitment = prover.commit(&evals).unwrap();
 
         let mut modified_evals = evals.clone();
         let new_coeff = random_field_elem_neq(&mut rng, modified_evals.coeffs[2]);
@@ -433,7 +1004,7 @@ mod tests {
         witness: &KZGWitness,
     ) {
         assert!(
-            verifier.verify_eval(point, &commitment, &witness),
+            verifier.verify_eval(point, commitment, witness),
             "verify_eval failed for point {:#?}, commitment {:#?}, and witness {:#?}",
             point,
             commitment,
@@ -447,21 +1018,21 @@ mod tests {
         commitment: &KZGCommitment,
         witness: &KZGWitness,
     ) {
-        assert!(!verifier.verify_eval(point, &commitment, &witness), "expected verify_eval to fail for for point {:#?}, commitment {:#?}, and witness {:#?}, but it didn't", point, commitment, witness);
+        assert!(!verifier.verify_eval(point, commitment, witness), "expected verify_eval to fail for for point {:#?}, commitment {:#?}, and witness {:#?}, but it didn't", point, commitment, witness);
     }
 
     #[test]
     fn test_eval_basic() {
         let mut rng = SmallRng::from_seed(RNG_SEED);
         let params = test_setup(&mut rng, 16);
-        let lagrange_basis = compute_lagrange_basis(&params);
+        let lagrange_basis = compute_lagrange_basis(&params).unwrap();
 
         let (prover, verifier) = test_participants(&params, lagrange_basis.0.as_slice(), lagrange_basis.1.as_slice());
 
         let evals = random_evals(&mut rng, prover.d);
-        let commitment = prover.commit(&evals);
+        let commitment = prover.commit(&evals).unwrap();
 
-        let witness = prover.create_witness(&evals, 3);
+        let witness = prover.create_witness(&evals, 3).unwrap();
         assert_verify_eval(&verifier, (3, evals.coeffs[3]), &commitment, &witness);
 
         let y_prime = random_field_elem_neq(&mut rng, evals.coeffs[3]);
@@ -472,13 +1043,334 @@ mod tests {
     fn test_eval_all() {
         let mut rng = SmallRng::from_seed(RNG_SEED);
         let params = test_setup(&mut rng, 16);
-        let lagrange_basis = compute_lagrange_basis(&params);
+        let lagrange_basis = compute_lagrange_basis(&params).unwrap();
 
-        let (mut prover, verifier) = test_participants(&params, lagrange_basis.0.as_slice(), lagrange_basis.1.as_slice());
+        let (prover, verifier) = test_participants(&params, lagrange_basis.0.as_slice(), lagrange_basis.1.as_slice());
 
         let evals = random_evals(&mut rng, prover.d);
-        let commitment = prover.commit(&evals);
+        let commitment = prover.commit(&evals).unwrap();
         let witness = prover.create_witness_all();
         assert!(verifier.verify_eval_all(evals.coeffs.as_ref(), &commitment, &witness))
     }
+
+    #[test]
+    fn test_lagrange_basis() {
+        let s = Scalar::from(1234);
+        let params = setup(s, 8);
+        let (gs, hs) = compute_lagrange_basis(&params).unwrap();
+        let (_, _, omega) = EvaluationDomain::compute_omega(8).unwrap();
+        let xs: Vec<Scalar> = (0..8).map(|i| omega.pow_vartime([i as u64])).collect();
+
+        for i in 0..8 {
+            let l_s = (0..8)
+                .filter(|&j| j != i)
+                .fold(Scalar::one(), |acc, j| acc * (s - xs[j]) * (xs[i] - xs[j]).invert().unwrap());
+            assert_eq!(gs[i], G1Projective::generator() * l_s);
+            assert_eq!(hs[i], G2Projective::generator() * l_s);
+        }
+
+        assert!(matches!(
+            compute_lagrange_basis_for(&params, 6, Scalar::one()),
+            Err(KZGError::UnsupportedDomain(6))
+        ));
+    }
+
+    #[test]
+    fn test_from_basis() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let basis = LagrangeBasis::from_params(&params).unwrap();
+
+        let prover = KZGProverEvalForm::from_basis(&params, &basis);
+        let verifier = KZGVerifierEvalForm::from_basis(&params, &basis).unwrap();
+
+        let evals = random_evals(&mut rng, prover.d);
+        let commitment = prover.commit(&evals).unwrap();
+        assert_verify_poly(&verifier, &commitment, &evals);
+
+        let witness = prover.create_witness(&evals, 5).unwrap();
+        assert_verify_eval(&verifier, (5, evals.coeffs[5]), &commitment, &witness);
+    }
+
+    #[test]
+    fn test_multiple_domains() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let shift = Scalar::multiplicative_generator();
+        let full = LagrangeBasis::from_params(&params).unwrap();
+        let small = LagrangeBasis::new(&params, 4, Scalar::one()).unwrap();
+        let coset = LagrangeBasis::new(&params, 8, shift).unwrap();
+
+        let mut prover = KZGProverEvalForm::from_basis(&params, &full);
+        let mut verifier = KZGVerifierEvalForm::from_basis(&params, &full).unwrap();
+        for basis in [&small, &coset].iter() {
+            prover.add_basis(basis);
+            verifier.add_basis(basis);
+        }
+
+        for &(d, shift) in [(4, Scalar::one()), (8, shift), (16, Scalar::one())].iter() {
+            let evals = random_evals(&mut rng, d).with_shift(shift);
+            let commitment = prover.commit(&evals).unwrap();
+            assert_verify_poly(&verifier, &commitment, &evals);
+
+            let witness = prover.create_witness(&evals, 1).unwrap();
+            assert!(verifier.verify_eval_over(d, shift, (1, evals.coeffs[1]), &commitment, &witness).unwrap());
+
+            let y_prime = random_field_elem_neq(&mut rng, evals.coeffs[1]);
+            assert!(!verifier.verify_eval_over(d, shift, (1, y_prime), &commitment, &witness).unwrap());
+        }
+
+        // no basis for the coset of size 4, or for size 2
+        let evals = random_evals(&mut rng, 4).with_shift(shift);
+        assert!(matches!(prover.commit(&evals), Err(KZGError::UnsupportedDomain(4))));
+        assert!(matches!(prover.create_witness(&evals, 1), Err(KZGError::UnsupportedDomain(4))));
+        let commitment = prover.commit(&random_evals(&mut rng, 4)).unwrap();
+        assert!(matches!(
+            verifier.verify_eval_over(2, Scalar::one(), (1, Scalar::one()), &commitment, &G1Affine::identity()),
+            Err(KZGError::UnsupportedDomain(2))
+        ));
+    }
+
+    #[test]
+    fn test_all_witnesses() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let shift = Scalar::multiplicative_generator();
+        let full = LagrangeBasis::from_params(&params).unwrap();
+        let coset = LagrangeBasis::new(&params, 8, shift).unwrap();
+
+        let mut prover = KZGProverEvalForm::from_basis(&params, &full);
+        prover.add_basis(&coset);
+
+        for &(d, shift) in [(16, Scalar::one()), (8, shift)].iter() {
+            let evals = random_evals(&mut rng, d).with_shift(shift);
+            let witnesses = prover.create_all_witnesses(&evals).unwrap();
+            assert_eq!(witnesses.len(), d);
+            for (i, w) in witnesses.iter().enumerate() {
+                assert_eq!(*w, prover.create_witness(&evals, i).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_cells() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let shift = Scalar::multiplicative_generator();
+        let basis = LagrangeBasis::new(&params, 16, shift).unwrap();
+
+        let prover = KZGProverEvalForm::from_basis(&params, &basis);
+        let verifier = KZGVerifierEvalForm::from_basis(&params, &basis).unwrap();
+        let evals = random_evals(&mut rng, 16).with_shift(shift);
+        let commitment = prover.commit(&evals).unwrap();
+
+        for &l in [1, 4].iter() {
+            let witnesses = prover.create_cell_witnesses(&evals, l).unwrap();
+            assert_eq!(witnesses.len(), 16 / l);
+
+            for (i, w) in witnesses.iter().enumerate() {
+                let mut ys: Vec<Scalar> = (0..l).map(|j| evals.coeffs[i + j * 16 / l]).collect();
+                assert!(verifier.verify_cell(l, i, &ys, &commitment, w).unwrap());
+
+                ys[l - 1] += Scalar::one();
+                assert!(!verifier.verify_cell(l, i, &ys, &commitment, w).unwrap());
+            }
+        }
+
+        // cells that don't exist
+        let witnesses = prover.create_cell_witnesses(&evals, 4).unwrap();
+        let ys: Vec<Scalar> = (0..4).map(|j| evals.coeffs[j * 4]).collect();
+        assert!(!verifier.verify_cell(4, 4, &ys, &commitment, &witnesses[0]).unwrap());
+        assert!(!verifier.verify_cell(3, 0, &ys[..3], &commitment, &witnesses[0]).unwrap());
+        assert!(!verifier.verify_cell(32, 0, &ys, &commitment, &witnesses[0]).unwrap());
+        for &l in [0, 3, 32].iter() {
+            assert!(matches!(
+                prover.create_cell_witnesses(&evals, l),
+                Err(KZGError::UnsupportedDomain(n)) if n == l
+            ));
+        }
+    }
+
+    #[test]
+    fn test_updates() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let shift = Scalar::multiplicative_generator();
+
+        for &shift in [Scalar::one(), shift].iter() {
+            let basis = LagrangeBasis::new(&params, 16, shift).unwrap();
+            let keys = UpdateKeys::new(&params, 16, shift).unwrap();
+            let prover = KZGProverEvalForm::from_basis(&params, &basis);
+
+            let mut evals = random_evals(&mut rng, 16).with_shift(shift);
+            let mut commitment = prover.commit(&evals).unwrap();
+            let mut witnesses: Vec<KZGWitness> = [2, 5].iter().map(|&j| prover.create_witness(&evals, j).unwrap()).collect();
+
+            // a write to an entry with a witness, and to one without
+            for &i in [5, 11].iter() {
+                let delta: Scalar = rng.gen::<u64>().into();
+                evals.coeffs[i] += delta;
+                commitment = prover.update_commitment(&commitment, i, delta).unwrap();
+                for (w, &j) in witnesses.iter_mut().zip([2, 5].iter()) {
+                    *w = keys.update_witness(w, j, i, delta).unwrap();
+                }
+
+                assert_eq!(commitment, prover.commit(&evals).unwrap());
+                assert_eq!(witnesses[0], prover.create_witness(&evals, 2).unwrap());
+                assert_eq!(witnesses[1], prover.create_witness(&evals, 5).unwrap());
+            }
+
+            assert!(matches!(
+                prover.update_commitment(&commitment, 16, Scalar::one()),
+                Err(KZGError::InvalidIndex(16))
+            ));
+            assert!(matches!(
+                keys.update_witness(&witnesses[0], 2, 16, Scalar::one()),
+                Err(KZGError::InvalidIndex(16))
+            ));
+            assert!(matches!(
+                keys.update_witness(&witnesses[0], 16, 2, Scalar::one()),
+                Err(KZGError::InvalidIndex(16))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_subvector() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let basis = LagrangeBasis::new(&params, 16, Scalar::multiplicative_generator()).unwrap();
+
+        let prover = KZGProverEvalForm::from_basis(&params, &basis);
+        let verifier = KZGVerifierEvalForm::from_basis(&params, &basis).unwrap();
+        let evals = random_evals(&mut rng, 16).with_shift(basis.shift());
+        let commitment = prover.commit(&evals).unwrap();
+
+        let all: Vec<usize> = (0..16).collect();
+        for indices in [vec![6], vec![9, 1, 4, 15], all].iter() {
+            let witnesses: Vec<(usize, KZGWitness)> =
+                indices.iter().map(|&i| (i, prover.create_witness(&evals, i).unwrap())).collect();
+            let witness = prover.aggregate_witnesses(&witnesses).unwrap();
+
+            let mut values: Vec<Scalar> = indices.iter().map(|&i| evals.coeffs[i]).collect();
+            assert!(verifier.verify_subvector(indices, &values, &commitment, &witness).unwrap());
+
+            // h^A_I(tau) has to vanish on all of the indices
+            if indices.len() > 1 {
+                let ha = prover.aggregate_witnesses(&witnesses[1..]).unwrap().vanishing();
+                let bad = KZGSubvectorWitness::new(witness.elem(), ha);
+                assert!(!verifier.verify_subvector(indices, &values, &commitment, &bad).unwrap());
+            }
+
+            values[0] += Scalar::one();
+            assert!(!verifier.verify_subvector(indices, &values, &commitment, &witness).unwrap());
+        }
+
+        let repeated = [(3, G1Affine::identity()), (3, G1Affine::identity())];
+        assert!(matches!(prover.aggregate_witnesses(&repeated), Err(KZGError::InvalidIndex(3))));
+
+        // the verifier only needs the G1 Lagrange basis and h, h^tau
+        let (lagrange_basis_g, _) = compute_lagrange_basis(&params).unwrap();
+        let short = KZGParams {
+            gs: params.gs.clone(),
+            hs: params.hs[..2].to_vec(),
+        };
+        let prover = KZGProverEvalForm::new(&params, &lagrange_basis_g);
+        let verifier = KZGVerifierEvalForm::new(&short, &lagrange_basis_g, &[]).unwrap();
+        let evals = random_evals(&mut rng, 16);
+        let commitment = prover.commit(&evals).unwrap();
+        let witnesses: Vec<(usize, KZGWitness)> =
+            [2, 7, 11].iter().map(|&i| (i, prover.create_witness(&evals, i).unwrap())).collect();
+        let witness = prover.aggregate_witnesses(&witnesses).unwrap();
+        let values = [evals.coeffs[2], evals.coeffs[7], evals.coeffs[11]];
+        assert!(verifier.verify_subvector(&[2, 7, 11], &values, &commitment, &witness).unwrap());
+
+        // but the prover needs G2 powers up to |I|
+        let prover = KZGProverEvalForm::new(&short, &lagrange_basis_g);
+        assert!(matches!(
+            prover.aggregate_witnesses(&witnesses),
+            Err(KZGError::NotEnoughG2Powers { needed: 4, available: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_eval_batched() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let shift = Scalar::multiplicative_generator();
+        let full = LagrangeBasis::from_params(&params).unwrap();
+        let coset = LagrangeBasis::new(&params, 8, shift).unwrap();
+
+        let mut prover = KZGProverEvalForm::from_basis(&params, &full);
+        let mut verifier = KZGVerifierEvalForm::from_basis(&params, &full).unwrap();
+        prover.add_basis(&coset);
+        verifier.add_basis(&coset);
+
+        for &(d, shift) in [(16, Scalar::one()), (8, shift)].iter() {
+            let evals = random_evals(&mut rng, d).with_shift(shift);
+            let commitment = prover.commit(&evals).unwrap();
+
+            let all: Vec<usize> = (0..d).collect();
+            for indices in [vec![3], vec![0, 5, 2, 7], all].iter() {
+                let witness = prover.create_witness_batched(&evals, indices).unwrap();
+                let mut values: Vec<Scalar> = indices.iter().map(|&i| evals.coeffs[i]).collect();
+                assert!(verifier.verify_eval_batched_over(d, shift, indices, &values, &commitment, &witness).unwrap());
+
+                // the quotient is the same one the aggregated witnesses commit to
+                if d == 16 {
+                    let witnesses: Vec<(usize, KZGWitness)> =
+                        indices.iter().map(|&i| (i, prover.create_witness(&evals, i).unwrap())).collect();
+                    assert_eq!(witness.elem(), prover.aggregate_witnesses(&witnesses).unwrap().elem());
+                }
+
+                // an r that matches the values but not the polynomial elsewhere
+                if let Some(k) = (0..d).find(|k| !indices.contains(k)) {
+                    let mut r = witness.polynomial().clone();
+                    r.coeffs[k] += Scalar::one();
+                    let bad = KZGBatchWitnessEvalForm::new(r, witness.elem());
+                    assert!(!verifier.verify_eval_batched_over(d, shift, indices, &values, &commitment, &bad).unwrap());
+                }
+
+                // an r with too few evaluations
+                let mut r = witness.polynomial().clone();
+                r.coeffs.pop();
+                let bad = KZGBatchWitnessEvalForm::new(r, witness.elem());
+                assert!(!verifier.verify_eval_batched_over(d, shift, indices, &values, &commitment, &bad).unwrap());
+
+                // the verifier picks the domain, not the witness
+                assert_eq!(verifier.verify_eval_batched(indices, &values, &commitment, &witness).unwrap(), d == 16);
+
+                values[0] += Scalar::one();
+                assert!(!verifier.verify_eval_batched_over(d, shift, indices, &values, &commitment, &witness).unwrap());
+            }
+        }
+
+        let witness = KZGBatchWitnessEvalForm::new(random_evals(&mut rng, 4), G1Affine::identity());
+        let values = [witness.polynomial().coeffs[0]];
+        assert!(matches!(
+            verifier.verify_eval_batched_over(4, Scalar::one(), &[0], &values, &G1Affine::identity(), &witness),
+            Err(KZGError::UnsupportedDomain(4))
+        ));
+    }
+
+    #[test]
+    fn test_eval_many() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_setup(&mut rng, 16);
+        let basis = LagrangeBasis::from_params(&params).unwrap();
+
+        let prover = KZGProverEvalForm::from_basis(&params, &basis);
+        let verifier = KZGVerifierEvalForm::from_basis(&params, &basis).unwrap();
+
+        let mut openings: Vec<((usize, Scalar), KZGCommitment, KZGWitness)> = (0..6)
+            .map(|i| {
+                let evals = random_evals(&mut rng, prover.d);
+                ((i, evals.coeffs[i]), prover.commit(&evals).unwrap(), prover.create_witness(&evals, i).unwrap())
+            })
+            .collect();
+        assert!(verifier.verify_eval_many(&openings, &mut rng));
+
+        (openings[2].0).0 = 3;
+        assert!(!verifier.verify_eval_many(&openings, &mut rng));
+    }
 }
@@ -0,0 +1,192 @@
+//! Fixed-base windowed scalar multiplication, used for fast SRS generation.
+//!
+//! For a base point `B` and window size `w`, the table holds `k * 2^(w * j) * B` for every window
+//! `j` and every digit `1 <= k < 2^w`, in affine form. Multiplying `B` by a scalar is then one
+//! mixed addition per base-`2^w` digit of the scalar, with no doublings.
+//!
+//! The scalars are secret (powers of tau), so `mul` runs in constant time: every table entry of a
+//! window is read and the one for the digit is picked with `ConditionallySelectable`, and a zero
+//! digit adds the identity instead of being skipped.
+
+use blstrs::Scalar;
+use pairing::group::{
+    ff::{Field, PrimeField},
+    prime::{PrimeCurve, PrimeCurveAffine},
+};
+use subtle::{ConditionallySelectable, ConstantTimeEq};
+
+#[cfg(feature = "parallel")]
+use crate::utils::chunk_by_num_threads;
+use crate::{zeroize_bytes, zeroize_scalar};
+
+/// a BLS12-381 scalar has 255 bits
+const SCALAR_BITS: usize = Scalar::NUM_BITS as usize;
+
+#[derive(Clone, Debug)]
+pub struct FixedBaseTable<G: PrimeCurve> {
+    window: usize,
+    /// `table[j][k - 1] = k * 2^(window * j) * base`
+    table: Vec<Vec<G::Affine>>,
+}
+
+impl<G: PrimeCurve<Scalar = Scalar>> FixedBaseTable<G>
+where
+    G::Affine: ConditionallySelectable,
+{
+    /// builds a table for `base` with the given window size
+    pub fn new(base: G, window: usize) -> Self {
+        assert!(window > 0 && window < 32);
+
+        let num_windows = SCALAR_BITS.div_ceil(window);
+        let mut table = Vec::with_capacity(num_windows);
+
+        let mut window_base = base;
+        for _ in 0..num_windows {
+            let mut row = Vec::with_capacity((1 << window) - 1);
+            let mut acc = window_base;
+            for _ in 1..(1 << window) {
+                row.push(acc);
+                acc += window_base;
+            }
+            // acc is now 2^window * window_base
+            window_base = acc;
+
+            let mut row_affine = vec![G::Affine::identity(); row.len()];
+            G::batch_normalize(&row, &mut row_affine);
+            table.push(row_affine);
+        }
+
+        FixedBaseTable { window, table }
+    }
+
+    /// picks the window size minimizing the cost of building the table plus `num_scalars`
+    /// multiplications. Every multiplication does one addition and reads a whole row of `2^w - 1`
+    /// entries per window; reading an entry is counted as 1/16 of an addition.
+    pub fn best_window(num_scalars: usize) -> usize {
+        (1..=20)
+            .min_by_key(|&w| {
+                let num_windows = SCALAR_BITS.div_ceil(w);
+                num_windows * (16 * (1usize << w) + num_scalars * (16 + (1usize << w)))
+            })
+            .unwrap()
+    }
+
+    /// multiplies the base by `scalar` in constant time
+    pub fn mul(&self, scalar: &Scalar) -> G {
+        let mut bytes = scalar.to_bytes_le();
+        let mut acc = G::identity();
+
+        for (j, row) in self.table.iter().enumerate() {
+            let digit = get_bits(&bytes, j * self.window, self.window);
+            let mut entry = G::Affine::identity();
+            for (k, p) in row.iter().enumerate() {
+                entry.conditional_assign(p, digit.ct_eq(&(k as u64 + 1)));
+            }
+            acc += &entry;
+        }
+
+        zeroize_bytes(&mut bytes);
+        acc
+    }
+
+    /// multiplies the base by every scalar in `scalars`, in parallel under the `parallel` feature
+    pub fn batch_mul(&self, scalars: &[Scalar]) -> Vec<G>
+    where
+        G: Send,
+    {
+        let mut res = vec![G::identity(); scalars.len()];
+
+        #[cfg(feature = "parallel")]
+        rayon::scope(|scope| {
+            let chunk_size = chunk_by_num_threads(scalars.len());
+
+            for (res, scalars) in res.chunks_mut(chunk_size).zip(scalars.chunks(chunk_size)) {
+                scope.spawn(move |_scope| {
+                    for (r, s) in res.iter_mut().zip(scalars.iter()) {
+                        *r = self.mul(s);
+                    }
+                });
+            }
+        });
+
+        #[cfg(not(feature = "parallel"))]
+        for (r, s) in res.iter_mut().zip(scalars.iter()) {
+            *r = self.mul(s);
+        }
+
+        res
+    }
+}
+
+/// returns `len` bits of little-endian `bytes`, starting at bit `start`. Bits past the end are zero.
+/// Only branches on the (public) positions, not on the bits themselves.
+fn get_bits(bytes: &[u8], start: usize, len: usize) -> u64 {
+    let mut res = 0;
+    for i in 0..len {
+        let bit = start + i;
+        if let Some(byte) = bytes.get(bit / 8) {
+            res |= (((byte >> (bit % 8)) & 1) as u64) << i;
+        }
+    }
+    res
+}
+
+/// computes `1, s, s^2, ..., s^(n - 1)`, in parallel under the `parallel` feature. The running
+/// power is zeroized, but the caller has to zeroize the result.
+pub fn scalar_powers(s: &Scalar, n: usize) -> Vec<Scalar> {
+    let mut powers = vec![Scalar::one(); n];
+
+    #[cfg(feature = "parallel")]
+    rayon::scope(|scope| {
+        let chunk_size = chunk_by_num_threads(n);
+
+        for (i, chunk) in powers.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(move |_scope| {
+                let mut u = s.pow_vartime([(i * chunk_size) as u64]);
+                for p in chunk.iter_mut() {
+                    *p = u;
+                    u *= s;
+                }
+                zeroize_scalar(&mut u);
+            });
+        }
+    });
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        let mut u = Scalar::one();
+        for p in powers.iter_mut() {
+            *p = u;
+            u *= s;
+        }
+        zeroize_scalar(&mut u);
+    }
+
+    powers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blstrs::{G1Projective, G2Projective};
+    use pairing::group::Group;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[test]
+    fn test_fixed_base_mul() {
+        let mut rng = SmallRng::from_seed([69; 32]);
+        let scalars: Vec<Scalar> = (0..20).map(|_| Scalar::random(&mut rng)).collect();
+
+        for &window in [1, 3, 8].iter() {
+            let table = FixedBaseTable::new(G1Projective::generator(), window);
+            let res = table.batch_mul(&scalars);
+            for (r, s) in res.iter().zip(scalars.iter()) {
+                assert_eq!(*r, G1Projective::generator() * s);
+            }
+        }
+
+        let table = FixedBaseTable::new(G2Projective::generator(), 5);
+        assert_eq!(table.mul(&-Scalar::one()), -G2Projective::generator());
+        assert_eq!(table.mul(&Scalar::zero()), G2Projective::identity());
+    }
+}
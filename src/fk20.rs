@@ -0,0 +1,241 @@
+//! Amortized opening proofs (Feist-Khovratovich, "Fast amortized KZG proofs").
+//!
+//! The witness for `f` at `x` commits to `(f(X) - f(x)) / (X - x) = sum_t x^t h_t(X)`, where
+//! `h_t(X) = sum_{j > t} f_j X^(j - t - 1)`. The commitments `[h_t(tau)]` don't depend on `x`, and
+//! together they are the product of a Toeplitz matrix built from `f` with the powers of tau. That
+//! product is a convolution, so it takes one FFT of size `2n` over the scalars and two in G1, one
+//! of which only depends on the SRS and is precomputed. The witnesses at every point of a domain of
+//! size `n` are then the FFT of the `[h_t(tau)]`, so all `n` of them take O(n log n) group
+//! operations instead of `n` MSMs.
+//!
+//! The same works for multi-proofs over the cosets of the subgroup of size `l` (Danksharding's
+//! cells). The witness for the coset `x * mu_l` commits to the quotient of `f` by `X^l - x^l`, which
+//! is `sum_t x^(l * t) h_t(X)` with `h_t(X) = sum_{j >= l * (t + 1)} f_j X^(j - l * (t + 1))`.
+//! Splitting `f` and the powers of tau by residue mod `l` turns this into `l` Toeplitz products of
+//! size `n / l`, which share the final inverse FFT.
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use pairing::group::{ff::Field, prime::PrimeCurveAffine, Curve, Group};
+
+use crate::ft::{fft_in_place, ifft_in_place};
+use crate::polynomial::Polynomial;
+#[cfg(feature = "parallel")]
+use crate::utils::chunk_by_num_threads;
+use crate::{KZGError, KZGWitness, SrsBackend};
+
+/// the SRS-dependent half of FK20 for polynomials with at most `n` coefficients, opened over cosets
+/// of size `l`. Building it costs `l` FFTs of size `2n / l` in G1, so reuse it when computing the
+/// witnesses of many polynomials.
+#[derive(Debug, Clone)]
+pub struct FK20Table {
+    n: usize,
+    l: usize,
+    /// for every `r < l`, the FFT of `[tau^(l * (m - 2) + r)], ..., [tau^(l + r)], [tau^r]`, padded
+    /// with zeros to size `2m`, where `m = n / l`
+    srs_ffts: Vec<Vec<G1Projective>>,
+}
+
+impl FK20Table {
+    /// a table for single-point witnesses. `n` must be a power of two, and `params` must have at
+    /// least `n - 1` G1 powers. Returns `UnsupportedDomain(n)` otherwise
+    pub fn new<P: SrsBackend + ?Sized>(params: &P, n: usize) -> Result<Self, KZGError> {
+        Self::with_coset_size(params, n, 1)
+    }
+
+    /// a table for witnesses over the cosets of the subgroup of size `l`. `n` and `l` must be powers
+    /// of two with `l <= n`, or this returns `UnsupportedDomain` for the offending size, and `params`
+    /// must have at least `n - l` G1 powers
+    pub fn with_coset_size<P: SrsBackend + ?Sized>(params: &P, n: usize, l: usize) -> Result<Self, KZGError> {
+        if !n.is_power_of_two() {
+            return Err(KZGError::UnsupportedDomain(n));
+        }
+        if !l.is_power_of_two() || l > n {
+            return Err(KZGError::UnsupportedDomain(l));
+        }
+        let gs = params.g1_powers();
+        if gs.len() < n - l {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
+
+        let m = n / l;
+        let mut srs_ffts = Vec::with_capacity(l);
+        for r in 0..l {
+            let mut srs_fft = vec![G1Projective::identity(); 2 * m];
+            for (t, s) in srs_fft.iter_mut().take(m - 1).enumerate() {
+                *s = gs[l * (m - 2 - t) + r];
+            }
+            fft_in_place(&mut srs_fft)?;
+            srs_ffts.push(srs_fft);
+        }
+
+        Ok(FK20Table { n, l, srs_ffts })
+    }
+
+    pub fn domain_size(&self) -> usize {
+        self.n
+    }
+
+    pub fn coset_size(&self) -> usize {
+        self.l
+    }
+
+    /// returns a witness for `polynomial` over the coset `shift * omega^i * mu_l` for every
+    /// `i < n / l`, where `omega` generates the subgroup of size `n` and `mu_l` is the subgroup of
+    /// size `l`. The `i`th coset holds the points `shift * omega^(i + j * n / l)` for `j < l`. For
+    /// `l = 1`, these are the witnesses at every `shift * omega^i`. `polynomial` must have at most
+    /// `n` coefficients.
+    pub fn open_all(&self, polynomial: &Polynomial, shift: Scalar) -> Result<Vec<KZGWitness>, KZGError> {
+        let (l, m) = (self.l, self.n / self.l);
+        let degree = Polynomial::compute_degree(&polynomial.coeffs, polynomial.degree());
+        if degree >= self.n {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
+
+        // h_t = sum_r sum_j f_(l * (t + 1 + j) + r) * [tau^(l * j + r)]. For every r that's entry
+        // m - 1 + t of the convolution of the f_(l * k + r) with the reversed [tau^(l * j + r)]. The
+        // convolutions have 2m - 2 entries, so they don't wrap around.
+        let coeffs = &polynomial.coeffs[..degree + 1];
+        let mut hs = vec![G1Projective::identity(); 2 * m];
+        for (r, srs_fft) in self.srs_ffts.iter().enumerate() {
+            let mut fs = vec![Scalar::zero(); 2 * m];
+            for (f, c) in fs.iter_mut().zip(coeffs.iter().skip(r).step_by(l)) {
+                *f = *c;
+            }
+            fft_in_place(&mut fs)?;
+            mul_add_pointwise(&mut hs, srs_fft, &fs);
+        }
+        ifft_in_place(&mut hs)?;
+
+        // h_(m - 1) is zero, which pads the h_t to size m
+        let mut hs = hs.split_off(m - 1);
+        hs.truncate(m);
+
+        // the witness for the coset of x is sum_t (x^l)^t h_t, i.e. the evaluation at x^l of the
+        // polynomial with coefficients h_t. The x^l are shift^l times the m-th roots of unity, so
+        // that's the FFT of the shift^(l * t) h_t.
+        let shift_l = shift.pow_vartime([l as u64]);
+        if shift_l != Scalar::one() {
+            let mut u = Scalar::one();
+            for h in hs.iter_mut() {
+                *h *= u;
+                u *= shift_l;
+            }
+        }
+        fft_in_place(&mut hs)?;
+
+        let mut witnesses = vec![G1Affine::identity(); m];
+        G1Projective::batch_normalize(&hs, &mut witnesses);
+        Ok(witnesses)
+    }
+}
+
+/// `acc[i] += points[i] * scalars[i]`, in parallel under the `parallel` feature
+fn mul_add_pointwise(acc: &mut [G1Projective], points: &[G1Projective], scalars: &[Scalar]) {
+    #[cfg(feature = "parallel")]
+    rayon::scope(|scope| {
+        let chunk_size = chunk_by_num_threads(acc.len());
+
+        for ((acc, points), scalars) in acc
+            .chunks_mut(chunk_size)
+            .zip(points.chunks(chunk_size))
+            .zip(scalars.chunks(chunk_size))
+        {
+            scope.spawn(move |_scope| {
+                for ((a, p), s) in acc.iter_mut().zip(points.iter()).zip(scalars.iter()) {
+                    *a += p * s;
+                }
+            });
+        }
+    });
+
+    #[cfg(not(feature = "parallel"))]
+    for ((a, p), s) in acc.iter_mut().zip(points.iter()).zip(scalars.iter()) {
+        *a += p * s;
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::coeff_form::KZGProver;
+    use crate::ft::EvaluationDomain;
+    use crate::setup;
+    use pairing::group::ff::PrimeField;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    const RNG_SEED: [u8; 32] = [69; 32];
+
+    #[test]
+    fn test_open_all() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = setup(rng.gen::<u64>().into(), 16);
+        let prover = KZGProver::new(&params);
+
+        // a polynomial with fewer coefficients than the domain, over H and over a coset of H
+        let f = Polynomial::new((0..13).map(|_| Scalar::random(&mut rng)).collect());
+        let table = FK20Table::new(&params, 16).unwrap();
+        let (_, _, omega) = EvaluationDomain::compute_omega(16).unwrap();
+
+        for &shift in [Scalar::one(), Scalar::multiplicative_generator()].iter() {
+            let witnesses = table.open_all(&f, shift).unwrap();
+            assert_eq!(witnesses.len(), 16);
+
+            for (i, w) in witnesses.iter().enumerate() {
+                let x = shift * omega.pow_vartime([i as u64]);
+                assert_eq!(*w, prover.create_witness(&f, (x, f.eval(x)), None).unwrap().0);
+            }
+        }
+
+        assert_eq!(
+            prover.create_all_witnesses(&f, 16).unwrap(),
+            table.open_all(&f, Scalar::one()).unwrap()
+        );
+
+        let too_big = Polynomial::new((0..17).map(|_| Scalar::random(&mut rng)).collect());
+        assert!(matches!(
+            table.open_all(&too_big, Scalar::one()),
+            Err(KZGError::PolynomialDegreeTooLarge)
+        ));
+
+        // the domain has to be a nonempty subgroup
+        for &d in [0, 12].iter() {
+            assert!(matches!(FK20Table::new(&params, d), Err(KZGError::UnsupportedDomain(n)) if n == d));
+            assert!(matches!(
+                prover.create_all_witnesses(&f, d),
+                Err(KZGError::UnsupportedDomain(n)) if n == d
+            ));
+        }
+    }
+
+    #[test]
+    fn test_open_cosets() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = setup(rng.gen::<u64>().into(), 16);
+        let f = Polynomial::new((0..16).map(|_| Scalar::random(&mut rng)).collect());
+        let (_, _, omega) = EvaluationDomain::compute_omega(16).unwrap();
+
+        for &l in [1, 4, 16].iter() {
+            let table = FK20Table::with_coset_size(&params, 16, l).unwrap();
+            let shift = Scalar::multiplicative_generator();
+            let witnesses = table.open_all(&f, shift).unwrap();
+            assert_eq!(witnesses.len(), 16 / l);
+
+            // commit to the quotient by X^l - x^l directly
+            for (i, w) in witnesses.iter().enumerate() {
+                let x = shift * omega.pow_vartime([i as u64]);
+                let mut z = Polynomial::new_single_term(l);
+                z.coeffs[0] = -x.pow_vartime([l as u64]);
+                let (q, _) = f.long_division(&z);
+                assert_eq!(*w, KZGProver::new(&params).commit(&q, None).unwrap().0);
+            }
+        }
+
+        for &l in [0, 3, 32].iter() {
+            assert!(matches!(
+                FK20Table::with_coset_size(&params, 16, l),
+                Err(KZGError::UnsupportedDomain(n)) if n == l
+            ));
+        }
+    }
+}
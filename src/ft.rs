@@ -4,16 +4,21 @@ use std::ops::{AddAssign, MulAssign, SubAssign};
 
 use crate::polynomial::Polynomial;
 use crate::KZGError;
-use blstrs::Scalar;
+use blstrs::{G1Projective, G2Projective, Scalar};
+use pairing::group::Group;
 use pairing::group::ff::Field;
 use pairing::group::ff::PrimeField;
 
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
 #[cfg(feature = "parallel")]
 use crate::utils::chunk_by_num_threads;
 #[cfg(feature = "parallel")]
 use crate::utils::log2;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct EvaluationDomain {
     pub(crate) coeffs: Vec<Scalar>,
     pub(crate) d: usize,
@@ -22,6 +27,8 @@ pub struct EvaluationDomain {
     pub(crate) omegainv: Scalar,
     pub(crate) geninv: Scalar,
     pub(crate) minv: Scalar,
+    /// evaluations are taken over the coset `shift * H` of the subgroup `H` generated by `omega`
+    pub(crate) shift: Scalar,
 }
 
 impl From<EvaluationDomain> for Polynomial {
@@ -51,6 +58,10 @@ impl EvaluationDomain {
         self.coeffs.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
     // returns m, exp, and omega
     pub fn compute_omega(d: usize) -> Result<(usize, u32, Scalar), KZGError> {
         // Compute the size of our evaluation domain
@@ -70,11 +81,23 @@ impl EvaluationDomain {
         }
 
         // Compute omega, the 2^exp primitive root of unity
-        let omega = Scalar::root_of_unity().pow_vartime(&[1 << (Scalar::S - exp)]);
+        let omega = Scalar::root_of_unity().pow_vartime([1 << (Scalar::S - exp)]);
 
         Ok((m, exp, omega))
     }
 
+    pub fn shift(&self) -> Scalar {
+        self.shift
+    }
+
+    /// marks the coefficients as evaluations over the coset `shift * H` rather than `H` itself.
+    /// this only matters to eval-form commitments - the FFTs always work over `H`.
+    pub fn with_shift(mut self, shift: Scalar) -> Self {
+        assert!(!bool::from(shift.is_zero()));
+        self.shift = shift;
+        self
+    }
+
     pub fn clone_with_different_coeffs(&self, coeffs: Vec<Scalar>) -> EvaluationDomain {
         EvaluationDomain { coeffs, ..*self }
     }
@@ -88,6 +111,7 @@ impl EvaluationDomain {
             omegainv: omega.invert().unwrap(),
             geninv: Scalar::multiplicative_generator().invert().unwrap(),
             minv: Scalar::from(d as u64).invert().unwrap(),
+            shift: Scalar::one(),
         }
     }
 
@@ -105,6 +129,7 @@ impl EvaluationDomain {
             omegainv: omega.invert().unwrap(),
             geninv: Scalar::multiplicative_generator().invert().unwrap(),
             minv: Scalar::from(m as u64).invert().unwrap(),
+            shift: Scalar::one(),
         })
     }
 
@@ -146,7 +171,7 @@ impl EvaluationDomain {
 
             for (i, v) in self.coeffs.chunks_mut(chunk_size).enumerate() {
                 scope.spawn(move |_scope| {
-                    let mut u = g.pow_vartime(&[(i * chunk_size) as u64]);
+                    let mut u = g.pow_vartime([(i * chunk_size) as u64]);
                     for v in v.iter_mut() {
                         v.mul_assign(&u);
                         u.mul_assign(&g);
@@ -158,7 +183,7 @@ impl EvaluationDomain {
         #[cfg(not(feature = "parallel"))]
         {
             for (i, v) in self.coeffs.iter_mut().enumerate() {
-                let mut u = g.pow_vartime(&[i as u64]);
+                let mut u = g.pow_vartime([i as u64]);
                 v.mul_assign(&u);
                 u.mul_assign(&g);
             }
@@ -180,7 +205,7 @@ impl EvaluationDomain {
     /// This evaluates t(tau) for this domain, which is
     /// tau^m - 1 for these radix-2 domains.
     pub fn z(&self, tau: &Scalar) -> Scalar {
-        let mut tmp = tau.pow_vartime(&[self.coeffs.len() as u64]);
+        let mut tmp = tau.pow_vartime([self.coeffs.len() as u64]);
         tmp.sub_assign(&Scalar::one());
 
         tmp
@@ -271,7 +296,74 @@ impl EvaluationDomain {
     }
 }
 
-fn best_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32) {
+/// anything the FFT can operate on: field elements, or group elements with scalar multiplication
+pub trait FftElement:
+    Copy + Send + Sync + AddAssign + SubAssign + MulAssign<Scalar> + 'static
+{
+    fn group_zero() -> Self;
+}
+
+impl FftElement for Scalar {
+    fn group_zero() -> Self {
+        Scalar::zero()
+    }
+}
+
+impl FftElement for G1Projective {
+    fn group_zero() -> Self {
+        G1Projective::identity()
+    }
+}
+
+impl FftElement for G2Projective {
+    fn group_zero() -> Self {
+        G2Projective::identity()
+    }
+}
+
+/// in-place FFT of `a` over the domain of size `a.len()`, which must be a power of two
+pub fn fft_in_place<T: FftElement>(a: &mut [T]) -> Result<(), KZGError> {
+    if !a.len().is_power_of_two() {
+        return Err(KZGError::UnsupportedDomain(a.len()));
+    }
+    let (_, exp, omega) = EvaluationDomain::compute_omega(a.len())?;
+    best_fft(a, &omega, exp);
+    Ok(())
+}
+
+/// in-place inverse FFT of `a` over the domain of size `a.len()`, which must be a power of two.
+/// applied to `[g, g^tau, ..., g^(tau^(n - 1))]`, this yields the Lagrange basis `[g^L_i(tau)]`
+pub fn ifft_in_place<T: FftElement>(a: &mut [T]) -> Result<(), KZGError> {
+    if !a.len().is_power_of_two() {
+        return Err(KZGError::UnsupportedDomain(a.len()));
+    }
+    let (m, exp, omega) = EvaluationDomain::compute_omega(a.len())?;
+    best_fft(a, &omega.invert().unwrap(), exp);
+
+    let minv = Scalar::from(m as u64).invert().unwrap();
+
+    #[cfg(feature = "parallel")]
+    rayon::scope(|scope| {
+        let chunk_size = chunk_by_num_threads(a.len());
+
+        for v in a.chunks_mut(chunk_size) {
+            scope.spawn(move |_scope| {
+                for v in v {
+                    *v *= minv;
+                }
+            });
+        }
+    });
+
+    #[cfg(not(feature = "parallel"))]
+    for v in a.iter_mut() {
+        *v *= minv;
+    }
+
+    Ok(())
+}
+
+fn best_fft<T: FftElement>(a: &mut [T], omega: &Scalar, log_n: u32) {
     #[cfg(feature = "parallel")]
     {
         let log_cpus = log2(rayon::current_num_threads() as u64) as u32;
@@ -288,7 +380,7 @@ fn best_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32) {
 }
 
 #[allow(clippy::many_single_char_names)]
-fn serial_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32) {
+fn serial_fft<T: FftElement>(a: &mut [T], omega: &Scalar, log_n: u32) {
     fn bitreverse(mut n: u32, l: u32) -> u32 {
         let mut r = 0;
         for _ in 0..l {
@@ -310,18 +402,18 @@ fn serial_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32) {
 
     let mut m = 1;
     for _ in 0..log_n {
-        let w_m = omega.pow_vartime(&[u64::from(n / (2 * m))]);
+        let w_m = omega.pow_vartime([u64::from(n / (2 * m))]);
 
         let mut k = 0;
         while k < n {
             let mut w = Scalar::one();
             for j in 0..m {
                 let mut t = a[(k + j + m) as usize];
-                t.mul_assign(&w);
+                t.mul_assign(w);
                 let mut tmp = a[(k + j) as usize];
-                tmp.sub_assign(&t);
+                tmp.sub_assign(t);
                 a[(k + j + m) as usize] = tmp;
-                a[(k + j) as usize].add_assign(&t);
+                a[(k + j) as usize].add_assign(t);
                 w.mul_assign(&w_m);
             }
 
@@ -333,13 +425,13 @@ fn serial_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32) {
 }
 
 #[cfg(feature = "parallel")]
-fn parallel_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32, log_cpus: u32) {
+fn parallel_fft<T: FftElement>(a: &mut [T], omega: &Scalar, log_n: u32, log_cpus: u32) {
     assert!(log_n >= log_cpus);
 
     let num_cpus = 1 << log_cpus;
     let log_new_n = log_n - log_cpus;
-    let mut tmp = vec![vec![Scalar::zero(); 1 << log_new_n]; num_cpus];
-    let new_omega = omega.pow_vartime(&[num_cpus as u64]);
+    let mut tmp = vec![vec![T::group_zero(); 1 << log_new_n]; num_cpus];
+    let new_omega = omega.pow_vartime([num_cpus as u64]);
 
     rayon::scope(|scope| {
         let a = &*a;
@@ -347,16 +439,16 @@ fn parallel_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32, log_cpus: u32) {
         for (j, tmp) in tmp.iter_mut().enumerate() {
             scope.spawn(move |_scope| {
                 // Shuffle into a sub-FFT
-                let omega_j = omega.pow_vartime(&[j as u64]);
-                let omega_step = omega.pow_vartime(&[(j as u64) << log_new_n]);
+                let omega_j = omega.pow_vartime([j as u64]);
+                let omega_step = omega.pow_vartime([(j as u64) << log_new_n]);
 
                 let mut elt = Scalar::one();
                 for (i, tmp) in tmp.iter_mut().enumerate() {
                     for s in 0..num_cpus {
                         let idx = (i + (s << log_new_n)) % (1 << log_n);
                         let mut t = a[idx];
-                        t.mul_assign(&elt);
-                        tmp.add_assign(&t);
+                        t.mul_assign(elt);
+                        tmp.add_assign(t);
                         elt.mul_assign(&omega_step);
                     }
                     elt.mul_assign(&omega_j);
@@ -375,34 +467,15 @@ fn parallel_fft(a: &mut [Scalar], omega: &Scalar, log_n: u32, log_cpus: u32) {
 
         for (idx, a) in a.chunks_mut(chunk_size).enumerate() {
             scope.spawn(move |_scope| {
-                let mut idx = idx * chunk_size;
                 let mask = (1 << log_cpus) - 1;
-                for a in a {
+                for (idx, a) in (idx * chunk_size..).zip(a.iter_mut()) {
                     *a = tmp[idx & mask][idx >> log_cpus];
-                    idx += 1;
                 }
             });
         }
     });
 }
 
-#[cfg(all(feature = "serde_support", feature = "b12_381"))]
-use crate::wrapper_types::SerializablePrimeField;
-
-#[cfg(all(feature = "serde_support", feature = "b12_381"))]
-use bls12_381::Scalar;
-
-#[cfg(all(feature = "serde_support", feature = "b12_381"))]
-#[derive(Serialize, Deserialize)]
-pub struct SerializableEvaluationDomain {
-    coeffs: Vec<SerializablePrimeField<Scalar>>,
-    exp: u32,
-    omega: SerializablePrimeField<Scalar>,
-    omegainv: SerializablePrimeField<Scalar>,
-    geninv: SerializablePrimeField<Scalar>,
-    minv: SerializablePrimeField<Scalar>,
-}
-
 #[cfg(test)]
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 
@@ -411,8 +484,8 @@ use rand::{rngs::SmallRng, Rng, SeedableRng};
 #[test]
 fn polynomial_arith() {
     fn test_mul<R: Rng>(mut rng: &mut R) {
-        for coeffs_a in vec![1, 5, 10, 50] {
-            for coeffs_b in vec![1, 5, 10, 50] {
+        for coeffs_a in [1, 5, 10, 50] {
+            for coeffs_b in [1, 5, 10, 50] {
                 let a: Vec<_> = (0..coeffs_a).map(|_| Scalar::random(&mut rng)).collect();
                 let b: Vec<_> = (0..coeffs_b).map(|_| Scalar::random(&mut rng)).collect();
 
@@ -433,17 +506,6 @@ fn polynomial_arith() {
     test_mul(rng);
 }
 
-#[cfg(test)]
-fn random_evals(rng: &mut SmallRng, d: usize) -> EvaluationDomain {
-    let mut coeffs = vec![Scalar::zero(); d];
-
-    for i in 0..d {
-        coeffs[i] = rng.gen::<u64>().into();
-    }
-
-    EvaluationDomain::from_coeffs(coeffs).unwrap()
-}
-
 #[test]
 fn fft_composition() {
     use rand::RngCore;
@@ -507,3 +569,32 @@ fn parallel_fft_consistency() {
 
     test_consistency(rng);
 }
+
+#[test]
+fn group_fft_composition() {
+    let rng = &mut SmallRng::from_seed([69; 32]);
+
+    for log_d in 0..6 {
+        let d = 1 << log_d;
+        let scalars: Vec<Scalar> = (0..d).map(|_| Scalar::random(&mut *rng)).collect();
+        let points: Vec<G1Projective> = scalars.iter().map(|s| G1Projective::generator() * s).collect();
+
+        // the group FFT agrees with the scalar FFT "in the exponent"
+        let mut domain = EvaluationDomain::from_coeffs(scalars).unwrap();
+        domain.ifft();
+        let mut res = points.clone();
+        ifft_in_place(&mut res).unwrap();
+        for (r, s) in res.iter().zip(domain.coeffs.iter()) {
+            assert_eq!(*r, G1Projective::generator() * s);
+        }
+
+        fft_in_place(&mut res).unwrap();
+        assert_eq!(res, points);
+    }
+
+    for &d in [0, 3, 12].iter() {
+        let mut points = vec![G1Projective::generator(); d];
+        assert!(matches!(fft_in_place(&mut points), Err(KZGError::UnsupportedDomain(n)) if n == d));
+        assert!(matches!(ifft_in_place(&mut points), Err(KZGError::UnsupportedDomain(n)) if n == d));
+    }
+}
@@ -0,0 +1,299 @@
+//! An owned Lagrange basis that can be cached on disk instead of recomputed at startup.
+//!
+//! The on-disk format follows the one for `KZGParams` (see `serialization`):
+//!
+//! | field    | size     | description                                   |
+//! |----------|----------|-----------------------------------------------|
+//! | magic    | 4        | `b"KZGL"`                                     |
+//! | version  | 4        | format version, little-endian. Currently `1`  |
+//! | curve    | 4        | curve id, little-endian. `1` is BLS12-381     |
+//! | d        | 8        | domain size, little-endian                    |
+//! | omega    | 32       | generator of the domain, little-endian        |
+//! | shift    | 32       | coset shift, little-endian. `1` for no coset  |
+//! | gs       | 48 * d   | compressed G1 points                          |
+//! | hs       | 96 * d   | compressed G2 points                          |
+//! | checksum | 32       | SHA-256 of everything above                   |
+
+use blstrs::{G1Projective, G2Prepared, G2Projective, Scalar};
+use pairing::group::{
+    ff::{BatchInvert, Field},
+    Curve,
+};
+use rand_core::RngCore;
+use std::io::{Read, Write};
+
+use crate::eval_form::compute_lagrange_basis_for;
+use crate::ft::EvaluationDomain;
+use crate::serialization::{
+    read_g1s, read_g2s, read_header, read_u64, write_g1s, write_g2s, write_header, HashingReader,
+    HashingWriter,
+};
+use crate::{pairings_equal, KZGError, KZGParams};
+
+pub const LAGRANGE_MAGIC: &[u8; 4] = b"KZGL";
+
+/// `gs[i] = g^L_i(tau)` and `hs[i] = h^L_i(tau)`, where `L_i` is the `i`th Lagrange polynomial
+/// over the coset `shift * H` of the domain `H` of size `d` generated by `omega`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LagrangeBasis {
+    d: usize,
+    omega: Scalar,
+    shift: Scalar,
+    gs: Vec<G1Projective>,
+    hs: Vec<G2Projective>,
+}
+
+impl LagrangeBasis {
+    /// computes the basis for the domain of size `params.gs.len()`
+    pub fn from_params(params: &KZGParams) -> Result<Self, KZGError> {
+        Self::new(params, params.gs.len(), Scalar::one())
+    }
+
+    /// computes the basis for the coset `shift * H` of the domain `H` of size `d`. `d` can be any
+    /// power of two up to the number of powers in `params`.
+    pub fn new(params: &KZGParams, d: usize, shift: Scalar) -> Result<Self, KZGError> {
+        let (gs, hs) = compute_lagrange_basis_for(params, d, shift)?;
+        let (d, _, omega) = EvaluationDomain::compute_omega(d)?;
+        Ok(LagrangeBasis {
+            d,
+            omega,
+            shift,
+            gs,
+            hs,
+        })
+    }
+
+    pub fn domain_size(&self) -> usize {
+        self.d
+    }
+
+    pub fn omega(&self) -> Scalar {
+        self.omega
+    }
+
+    pub fn shift(&self) -> Scalar {
+        self.shift
+    }
+
+    pub fn g1_basis(&self) -> &[G1Projective] {
+        &self.gs
+    }
+
+    pub fn g2_basis(&self) -> &[G2Projective] {
+        &self.hs
+    }
+
+    /// checks that this is the basis derived from `params`, without recomputing it.
+    ///
+    /// Every polynomial `p` of degree less than `d` satisfies `sum_i p(x_i) L_i(X) = p(X)`, where
+    /// `x_i = shift * omega^i`. For random `r` and `rho` we take
+    /// `p(X) = q(X) + rho X^(d - 1)` with `q(X) = (X^(d - 1) - r^(d - 1)) / (X - r)`, so with
+    /// `F = sum_i p(x_i) gs[i]` the basis is correct iff
+    /// `e(F - rho params.gs[d - 1], h^tau - h^r) == e(params.gs[d - 1] - g^(r^(d - 1)), h)`, and
+    /// likewise in G2. That's one MSM and one pairing check per group. A basis that differs from
+    /// the real one passes with probability at most `d/|F|`.
+    pub fn verify_against<R: RngCore>(&self, params: &KZGParams, rng: &mut R) -> Result<(), KZGError> {
+        let (d, _, omega) = EvaluationDomain::compute_omega(self.d)?;
+        if d != self.d || omega != self.omega || self.gs.len() != d || self.hs.len() != d {
+            return Err(KZGError::MalformedParams);
+        }
+        if params.gs.len() < d || params.gs.len() < 2 {
+            return Err(KZGError::MalformedParams);
+        }
+        params.check_g2_powers(d.max(2))?;
+
+        // r must not be one of the x_i, i.e. r^d != shift^d
+        let shift_d = self.shift.pow_vartime([d as u64]);
+        let mut r = Scalar::random(&mut *rng);
+        while r.pow_vartime([d as u64]) == shift_d {
+            r = Scalar::random(&mut *rng);
+        }
+        let rho = Scalar::random(&mut *rng);
+        let r_d1 = r.pow_vartime([(d - 1) as u64]);
+
+        // p(x_i) = (x_i^(d - 1) - r^(d - 1)) / (x_i - r) + rho x_i^(d - 1), with x_i^(d - 1) = shift^d / x_i
+        let mut xs = Vec::with_capacity(d);
+        let mut x = self.shift;
+        for _ in 0..d {
+            xs.push(x);
+            x *= omega;
+        }
+        let mut denoms: Vec<Scalar> = xs.iter().map(|x| x - r).chain(xs.iter().copied()).collect();
+        denoms.iter_mut().batch_invert();
+        let (diff_invs, x_invs) = denoms.split_at(d);
+        let ps: Vec<Scalar> = diff_invs
+            .iter()
+            .zip(x_invs.iter())
+            .map(|(diff_inv, x_inv)| {
+                let x_d1 = shift_d * x_inv;
+                (x_d1 - r_d1) * diff_inv + rho * x_d1
+            })
+            .collect();
+
+        let (g, h) = (params.gs[0], params.hs[0]);
+        let g_d1 = params.gs[d - 1];
+        let h_d1 = params.hs[d - 1];
+
+        let f = G1Projective::multi_exp(&self.gs, &ps);
+        let tau_minus_r: G2Prepared = (params.hs[1] - h * r).to_affine().into();
+        if !pairings_equal(
+            &(f - g_d1 * rho).to_affine(),
+            &tau_minus_r,
+            &(g_d1 - g * r_d1).to_affine(),
+            &h.to_affine().into(),
+        ) {
+            return Err(KZGError::MalformedParams);
+        }
+
+        let f = G2Projective::multi_exp(&self.hs, &ps);
+        if !pairings_equal(
+            &(params.gs[1] - g * r).to_affine(),
+            &(f - h_d1 * rho).to_affine().into(),
+            &g.to_affine(),
+            &(h_d1 - h * r_d1).to_affine().into(),
+        ) {
+            return Err(KZGError::MalformedParams);
+        }
+
+        Ok(())
+    }
+
+    /// writes the basis in the format described in the module docs
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), KZGError> {
+        let mut writer = HashingWriter::new(writer);
+
+        write_header(&mut writer, LAGRANGE_MAGIC)?;
+        writer.write_all(&(self.d as u64).to_le_bytes())?;
+        writer.write_all(&self.omega.to_bytes_le())?;
+        writer.write_all(&self.shift.to_bytes_le())?;
+        write_g1s(&mut writer, &self.gs)?;
+        write_g2s(&mut writer, &self.hs)?;
+
+        writer.finish()
+    }
+
+    /// reads a basis written by `write_to`, checking that every point is in the prime-order
+    /// subgroup. This does not check that the basis belongs to any particular `KZGParams` - use
+    /// `verify_against` for that.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, KZGError> {
+        Self::read_from_inner(reader, true)
+    }
+
+    /// like `read_from`, but skips the subgroup checks. Only use this for files you wrote yourself.
+    pub fn read_from_unchecked<R: Read>(reader: R) -> Result<Self, KZGError> {
+        Self::read_from_inner(reader, false)
+    }
+
+    fn read_from_inner<R: Read>(reader: R, checked: bool) -> Result<Self, KZGError> {
+        let mut reader = HashingReader::new(reader);
+
+        read_header(&mut reader, LAGRANGE_MAGIC)?;
+        let d = read_u64(&mut reader)? as usize;
+        if !d.is_power_of_two() {
+            return Err(KZGError::MalformedSetupFile(format!(
+                "domain size {} is not a power of two",
+                d
+            )));
+        }
+
+        let mut bytes = [0u8; 32];
+        reader.read_exact(&mut bytes)?;
+        let omega: Option<Scalar> = Scalar::from_bytes_le(&bytes).into();
+        let (_, _, expected_omega) = EvaluationDomain::compute_omega(d)?;
+        if omega != Some(expected_omega) {
+            return Err(KZGError::MalformedSetupFile("unexpected omega".to_string()));
+        }
+
+        reader.read_exact(&mut bytes)?;
+        let shift: Option<Scalar> = Scalar::from_bytes_le(&bytes).into();
+        let shift = match shift {
+            Some(shift) if !bool::from(shift.is_zero()) => shift,
+            _ => return Err(KZGError::MalformedSetupFile("invalid shift".to_string())),
+        };
+
+        let gs = read_g1s(&mut reader, d, checked)?;
+        let hs = read_g2s(&mut reader, d, checked)?;
+
+        reader.finish()?;
+
+        Ok(LagrangeBasis {
+            d,
+            omega: expected_omega,
+            shift,
+            gs,
+            hs,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::setup;
+    use pairing::group::Group;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    const RNG_SEED: [u8; 32] = [69; 32];
+
+    #[test]
+    fn test_roundtrip_and_verify() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup(s, 16);
+        let basis = LagrangeBasis::from_params(&params).unwrap();
+        assert!(basis.verify_against(&params, &mut rng).is_ok());
+
+        let mut bytes = Vec::new();
+        basis.write_to(&mut bytes).unwrap();
+        let read = LagrangeBasis::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read, basis);
+
+        // a basis for different parameters is rejected
+        let other = setup(s + Scalar::one(), 16);
+        assert!(matches!(
+            read.verify_against(&other, &mut rng),
+            Err(KZGError::MalformedParams)
+        ));
+
+        // so is a tampered one
+        let mut tampered = basis.clone();
+        tampered.hs[3] += G2Projective::generator();
+        assert!(tampered.verify_against(&params, &mut rng).is_err());
+        let mut tampered = basis.clone();
+        tampered.gs.swap(2, 5);
+        assert!(tampered.verify_against(&params, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_smaller_domain_on_coset() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup(s, 16);
+        let shift = Scalar::from(5);
+
+        let basis = LagrangeBasis::new(&params, 4, shift).unwrap();
+        assert!(basis.verify_against(&params, &mut rng).is_ok());
+
+        // L_i(tau) for the coset, computed directly
+        let xs: Vec<Scalar> = (0..4).map(|i| shift * basis.omega().pow_vartime([i as u64])).collect();
+        for i in 0..4 {
+            let mut l = Scalar::one();
+            for j in (0..4).filter(|&j| j != i) {
+                l *= (s - xs[j]) * (xs[i] - xs[j]).invert().unwrap();
+            }
+            assert_eq!(basis.g1_basis()[i], G1Projective::generator() * l);
+        }
+
+        let mut bytes = Vec::new();
+        basis.write_to(&mut bytes).unwrap();
+        assert_eq!(LagrangeBasis::read_from(bytes.as_slice()).unwrap(), basis);
+
+        // the basis for H itself is not the basis for the coset
+        let mut wrong = LagrangeBasis::new(&params, 4, Scalar::one()).unwrap();
+        wrong.shift = shift;
+        assert!(wrong.verify_against(&params, &mut rng).is_err());
+
+        assert!(LagrangeBasis::new(&params, 32, Scalar::one()).is_err());
+    }
+}
@@ -1,11 +1,31 @@
-use blstrs::{G1Affine, G1Projective, G2Projective, Scalar};
-use pairing::group::{Curve, Group, prime::PrimeCurveAffine};
+use blstrs::{Bls12, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Scalar};
+use pairing::group::{ff::Field, Curve, Group, prime::PrimeCurveAffine};
+use pairing::{MillerLoopResult, MultiMillerLoop};
+use rand_core::{CryptoRng, RngCore};
+use std::ptr;
+use std::sync::atomic;
 use thiserror::Error;
 
+use crate::coeff_form::KZGHidingParams;
+use crate::fixed_base::FixedBaseTable;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+pub mod ceremony;
 pub mod coeff_form;
 pub mod eval_form;
+pub mod fixed_base;
+pub mod fk20;
 pub mod ft;
+pub mod lagrange;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod polynomial;
+pub mod ptau;
+pub mod serialization;
+pub mod transcript;
+pub mod trusted_setup;
 pub mod utils;
 
 /// parameters from tested setup
@@ -18,6 +38,151 @@ pub struct KZGParams {
     pub hs: Vec<G2Projective>,
 }
 
+/// where the powers of tau used by `KZGProver` and `KZGVerifier` live.
+/// `KZGParams` keeps them in memory, `mmap::MmapParams` reads them straight out of a shared file.
+pub trait SrsBackend {
+    /// g, g^alpha^1, g^alpha^2, ...
+    fn g1_powers(&self) -> &[G1Projective];
+    /// h, h^alpha^1, h^alpha^2, ...
+    fn g2_powers(&self) -> &[G2Projective];
+}
+
+impl SrsBackend for KZGParams {
+    fn g1_powers(&self) -> &[G1Projective] {
+        &self.gs
+    }
+
+    fn g2_powers(&self) -> &[G2Projective] {
+        &self.hs
+    }
+}
+
+impl KZGParams {
+    /// returns an error if there are fewer than `needed` G2 powers
+    pub fn check_g2_powers(&self, needed: usize) -> Result<(), KZGError> {
+        check_g2_powers(self, needed)
+    }
+
+    /// checks that these are well-formed parameters, i.e. that there is a single tau with
+    /// `gs[i] = g^{tau^i}` and `hs[i] = h^{tau^i}`, where g and h are the standard generators.
+    /// Also rejects identity points and points outside the prime-order subgroup.
+    ///
+    /// Consecutive powers are checked with a random linear combination drawn from `rng`:
+    /// e(sum_i rho_i gs[i + 1], h) == e(sum_i rho_i gs[i], hs[1]), and likewise for hs, so this costs
+    /// two MSMs per group and two pairing checks rather than a pairing per point.
+    pub fn verify<R: RngCore>(&self, rng: &mut R) -> Result<(), KZGError> {
+        let (gs, hs) = (&self.gs, &self.hs);
+        if gs.len() < 2 || hs.len() < 2 {
+            return Err(KZGError::MalformedParams);
+        }
+        if gs[0] != G1Projective::generator() || hs[0] != G2Projective::generator() {
+            return Err(KZGError::MalformedParams);
+        }
+
+        let mut gs_affine = vec![G1Affine::identity(); gs.len()];
+        G1Projective::batch_normalize(gs, &mut gs_affine);
+        if !gs_affine
+            .iter()
+            .all(|g| !bool::from(g.is_identity()) && bool::from(g.is_torsion_free()))
+        {
+            return Err(KZGError::InvalidPoint);
+        }
+
+        let mut hs_affine = vec![G2Affine::identity(); hs.len()];
+        G2Projective::batch_normalize(hs, &mut hs_affine);
+        if !hs_affine
+            .iter()
+            .all(|h| !bool::from(h.is_identity()) && bool::from(h.is_torsion_free()))
+        {
+            return Err(KZGError::InvalidPoint);
+        }
+
+        let n = gs.len().max(hs.len()) - 1;
+        let rhos: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut *rng)).collect();
+
+        let g_lo = G1Projective::multi_exp(&gs[..gs.len() - 1], &rhos[..gs.len() - 1]);
+        let g_hi = G1Projective::multi_exp(&gs[1..], &rhos[..gs.len() - 1]);
+        let h: G2Prepared = hs_affine[0].into();
+        let h_tau: G2Prepared = hs_affine[1].into();
+        if !pairings_equal(&g_hi.to_affine(), &h, &g_lo.to_affine(), &h_tau) {
+            return Err(KZGError::MalformedParams);
+        }
+
+        let h_lo = G2Projective::multi_exp(&hs[..hs.len() - 1], &rhos[..hs.len() - 1]);
+        let h_hi = G2Projective::multi_exp(&hs[1..], &rhos[..hs.len() - 1]);
+        let h_hi: G2Prepared = h_hi.to_affine().into();
+        let h_lo: G2Prepared = h_lo.to_affine().into();
+        if !pairings_equal(&gs_affine[0], &h_hi, &gs_affine[1], &h_lo) {
+            return Err(KZGError::MalformedParams);
+        }
+
+        Ok(())
+    }
+
+    /// checks that these parameters are a truncation of `other`, i.e. that they're well-formed
+    /// (see `verify`) and are powers of the same tau with no more powers than `other` has.
+    /// `other` is assumed to have been verified already.
+    pub fn verify_truncation_of<R: RngCore>(
+        &self,
+        other: &KZGParams,
+        rng: &mut R,
+    ) -> Result<(), KZGError> {
+        if self.gs.len() > other.gs.len()
+            || self.hs.len() > other.hs.len()
+            || self.gs.len() < 2
+            || self.hs.len() < 2
+        {
+            return Err(KZGError::MalformedParams);
+        }
+
+        // well-formed parameters are determined by tau, and so by gs[1] and hs[1]
+        if self.gs[1] != other.gs[1] || self.hs[1] != other.hs[1] {
+            return Err(KZGError::MalformedParams);
+        }
+
+        self.verify(rng)
+    }
+}
+
+pub(crate) fn check_g2_powers<P: SrsBackend + ?Sized>(params: &P, needed: usize) -> Result<(), KZGError> {
+    let available = params.g2_powers().len();
+    if available < needed {
+        Err(KZGError::NotEnoughG2Powers { needed, available })
+    } else {
+        Ok(())
+    }
+}
+
+/// `h` and `h^alpha` in the form the Miller loop consumes. Every verifier pairs against these two on
+/// every check, so they are prepared once when the verifier is constructed.
+#[derive(Clone, Debug)]
+pub(crate) struct PreparedG2 {
+    pub(crate) h: G2Prepared,
+    pub(crate) h_alpha: G2Prepared,
+}
+
+impl PreparedG2 {
+    /// needs at least two G2 powers
+    pub(crate) fn new(hs: &[G2Projective]) -> Result<Self, KZGError> {
+        if hs.len() < 2 {
+            return Err(KZGError::NotEnoughG2Powers { needed: 2, available: hs.len() });
+        }
+
+        Ok(PreparedG2 {
+            h: hs[0].to_affine().into(),
+            h_alpha: hs[1].to_affine().into(),
+        })
+    }
+}
+
+/// checks `e(a, b) == e(c, d)` as `e(a, b) * e(-c, d) == 1`, with one multi-Miller loop and one
+/// final exponentiation instead of two full pairings
+pub(crate) fn pairings_equal(a: &G1Affine, b: &G2Prepared, c: &G1Affine, d: &G2Prepared) -> bool {
+    let neg_c = -c;
+    let res = Bls12::multi_miller_loop(&[(a, b), (&neg_c, d)]).final_exponentiation();
+    bool::from(res.is_identity())
+}
+
 /// the commitment - "C" in the paper. It's a single group element
 pub type KZGCommitment = G1Affine;
 /// A witness for a single element - "w_i" in the paper. It's a group element.
@@ -33,32 +198,248 @@ pub enum KZGError {
     BatchOpeningZeroRemainder,
     #[error("polynomial degree too large")]
     PolynomialDegreeTooLarge,
+    #[error("invalid proof of knowledge in ceremony contribution!")]
+    InvalidProofOfKnowledge,
+    #[error("ceremony update is inconsistent with the previous parameters!")]
+    InvalidUpdate,
+    #[error("parameters are not successive powers of a single secret!")]
+    MalformedParams,
+    #[error("malformed setup file: {0}")]
+    MalformedSetupFile(String),
+    #[error("point is not a valid group element!")]
+    InvalidPoint,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("not enough G2 powers: need {needed}, have {available}")]
+    NotEnoughG2Powers { needed: usize, available: usize },
+    #[error("polynomial exceeds its degree bound!")]
+    DegreeBoundExceeded,
+    #[error("index {0} is out of range or repeated!")]
+    InvalidIndex(usize),
+    #[error("unsupported evaluation domain of size {0}!")]
+    UnsupportedDomain(usize),
 }
 
+/// **insecure** deterministic setup for tests and benchmarks: whoever picks `s` can forge openings.
+/// Use `secure_setup` (or a ceremony, see `ceremony`) for parameters anyone else has to trust.
+#[deprecated(note = "insecure: whoever picks `s` can forge openings. Use `secure_setup` or `ToxicWaste`")]
 pub fn setup(s: Scalar, num_coeffs: usize) -> KZGParams {
-    let mut gs = vec![G1Projective::generator(); num_coeffs];
-    let mut hs = vec![G2Projective::generator(); num_coeffs];
+    fast_setup(&s, num_coeffs, num_coeffs)
+}
 
-    let mut curr = gs[0];
-    for g in gs.iter_mut().skip(1) {
-        *g = curr * s;
-        curr = *g;
+/// like `setup`, but with a separately chosen number of G2 powers. Single-point openings only need
+/// two G2 powers, and G2 is by far the slowest part of setup, so `num_g2` is usually much smaller
+/// than `num_g1`. Batched openings of `k` points need `k + 1` G2 powers.
+/// Just as insecure as `setup`. Returns `KZGError::MalformedParams` if either length is zero.
+#[deprecated(note = "insecure: whoever picks `s` can forge openings. Use `secure_setup` or `ToxicWaste`")]
+pub fn setup_asymmetric(s: Scalar, num_g1: usize, num_g2: usize) -> Result<KZGParams, KZGError> {
+    check_lengths(num_g1, num_g2)?;
+    Ok(fast_setup(&s, num_g1, num_g2))
+}
+
+/// every set of parameters needs at least one power in each group
+fn check_lengths(num_g1: usize, num_g2: usize) -> Result<(), KZGError> {
+    if num_g1 == 0 || num_g2 == 0 {
+        Err(KZGError::MalformedParams)
+    } else {
+        Ok(())
     }
+}
+
+/// `base, base^s, base^(s^2), ...`, `n` of them. The sequential reference for `fast_setup`
+#[cfg(test)]
+fn powers_of<G: Group<Scalar = Scalar>>(base: G, s: Scalar, n: usize) -> Vec<G> {
+    let mut curr = base;
+    (0..n)
+        .map(|_| {
+            let res = curr;
+            curr *= s;
+            res
+        })
+        .collect()
+}
 
-    let mut curr = hs[0];
-    for h in hs.iter_mut().skip(1) {
-        *h = curr * s;
-        curr = *h;
+/// computes `num_g1` G1 powers and `num_g2` G2 powers of `tau`. Rather than one full scalar
+/// multiplication per point, the powers of `tau` are computed first, then every point is one
+/// fixed-base multiplication of the generator using a precomputed window table (see
+/// `fixed_base`), parallelized under the `parallel` feature. The powers of `tau` are zeroized before returning.
+pub(crate) fn fast_setup(tau: &Scalar, num_g1: usize, num_g2: usize) -> KZGParams {
+    let mut powers = fixed_base::scalar_powers(tau, num_g1.max(num_g2));
+
+    let table = FixedBaseTable::new(G1Projective::generator(), FixedBaseTable::<G1Projective>::best_window(num_g1));
+    let gs = table.batch_mul(&powers[..num_g1]);
+
+    let table = FixedBaseTable::new(G2Projective::generator(), FixedBaseTable::<G2Projective>::best_window(num_g2));
+    let hs = table.batch_mul(&powers[..num_g2]);
+
+    for power in powers.iter_mut() {
+        zeroize_scalar(power);
     }
 
     KZGParams { gs, hs }
 }
 
-#[cfg(any(csprng_setup, test))]
-use rand::random;
+/// the secret "tau" behind a set of parameters. It can only be sampled from a CSPRNG, is never
+/// exposed or copied, and is zeroized when dropped - unlike the `Scalar` passed to `setup`.
+pub struct ToxicWaste {
+    tau: Scalar,
+}
+
+impl ToxicWaste {
+    /// samples a uniformly random, nonzero tau
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut tau = Scalar::random(&mut *rng);
+        while bool::from(tau.is_zero()) {
+            tau = Scalar::random(&mut *rng);
+        }
+
+        ToxicWaste { tau }
+    }
+
+    /// computes `num_g1` G1 powers and `num_g2` G2 powers of tau, consuming (and zeroizing) it.
+    /// Returns `KZGError::MalformedParams` if either length is zero.
+    pub fn into_params(self, num_g1: usize, num_g2: usize) -> Result<KZGParams, KZGError> {
+        check_lengths(num_g1, num_g2)?;
+        Ok(fast_setup(&self.tau, num_g1, num_g2))
+    }
+
+    /// like `into_params`, but also computes `num_g1` powers of tau on the hiding generator. This is
+    /// the only way to get `KZGHidingParams`: ceremony, Ethereum and `.ptau` setups don't have them.
+    pub fn into_params_with_hiding(
+        self,
+        num_g1: usize,
+        num_g2: usize,
+    ) -> Result<(KZGParams, KZGHidingParams), KZGError> {
+        check_lengths(num_g1, num_g2)?;
+        let params = fast_setup(&self.tau, num_g1, num_g2);
+        let hiding = KZGHidingParams::from_tau(&self.tau, num_g1);
+        Ok((params, hiding))
+    }
+}
+
+impl Drop for ToxicWaste {
+    fn drop(&mut self) {
+        zeroize_scalar(&mut self.tau);
+    }
+}
 
-#[cfg(any(csprng_setup, test))]
-pub fn csprng_setup(num_coeffs: usize) -> KZGParams {
-    let s: Scalar = random::<u64>().into();
-    setup(s, num_coeffs)
+impl std::fmt::Debug for ToxicWaste {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ToxicWaste(..)")
+    }
+}
+
+/// overwrites `s` with zero in a way the compiler won't optimize out
+pub(crate) fn zeroize_scalar(s: &mut Scalar) {
+    // SAFETY: `s` is a valid, aligned `&mut Scalar`
+    unsafe { ptr::write_volatile(s, Scalar::zero()) };
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// overwrites `bytes` with zeros in a way the compiler won't optimize out
+pub(crate) fn zeroize_bytes(bytes: &mut [u8]) {
+    for b in bytes.iter_mut() {
+        // SAFETY: `b` is a valid `&mut u8`
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// production setup: samples tau uniformly from the whole field using `rng`, and zeroizes it
+/// before returning. The `CryptoRng` bound keeps non-cryptographic RNGs out. Returns
+/// `KZGError::MalformedParams` if either length is zero.
+pub fn secure_setup<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    num_g1: usize,
+    num_g2: usize,
+) -> Result<KZGParams, KZGError> {
+    ToxicWaste::random(rng).into_params(num_g1, num_g2)
+}
+
+/// `secure_setup` using the operating system's RNG
+#[cfg(feature = "csprng_setup")]
+pub fn csprng_setup(num_coeffs: usize) -> Result<KZGParams, KZGError> {
+    secure_setup(&mut rand::rngs::OsRng, num_coeffs, num_coeffs)
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use blstrs::pairing;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_secure_setup() {
+        let mut rng = StdRng::from_seed([69; 32]);
+        let params = secure_setup(&mut rng, 8, 2).unwrap();
+        assert_eq!(params.gs.len(), 8);
+        assert_eq!(params.hs.len(), 2);
+
+        // e(g^tau, h) == e(g, h^tau)
+        assert_eq!(
+            pairing(&params.gs[1].to_affine(), &params.hs[0].to_affine()),
+            pairing(&params.gs[0].to_affine(), &params.hs[1].to_affine())
+        );
+        assert_ne!(params.gs[1], params.gs[0]);
+
+        assert!(matches!(secure_setup(&mut rng, 0, 2), Err(KZGError::MalformedParams)));
+        assert!(matches!(secure_setup(&mut rng, 8, 0), Err(KZGError::MalformedParams)));
+        assert!(matches!(
+            ToxicWaste::random(&mut rng).into_params_with_hiding(0, 2),
+            Err(KZGError::MalformedParams)
+        ));
+    }
+
+    #[test]
+    fn test_zeroize_scalar() {
+        let mut s = Scalar::from(42);
+        zeroize_scalar(&mut s);
+        assert_eq!(s, Scalar::zero());
+    }
+
+    #[test]
+    fn test_verify_params() {
+        let mut rng = StdRng::from_seed([69; 32]);
+        let params = secure_setup(&mut rng, 16, 4).unwrap();
+        assert!(params.verify(&mut rng).is_ok());
+
+        let truncated = KZGParams {
+            gs: params.gs[..8].to_vec(),
+            hs: params.hs[..2].to_vec(),
+        };
+        assert!(truncated.verify_truncation_of(&params, &mut rng).is_ok());
+        assert!(params.verify_truncation_of(&truncated, &mut rng).is_err());
+
+        let other = secure_setup(&mut rng, 8, 2).unwrap();
+        assert!(other.verify(&mut rng).is_ok());
+        assert!(other.verify_truncation_of(&params, &mut rng).is_err());
+
+        // swap two powers
+        let mut bad = params.clone();
+        bad.gs.swap(3, 4);
+        assert!(matches!(bad.verify(&mut rng), Err(KZGError::MalformedParams)));
+
+        // an inconsistent G2 power
+        let mut bad = params.clone();
+        bad.hs[3] = bad.hs[2];
+        assert!(matches!(bad.verify(&mut rng), Err(KZGError::MalformedParams)));
+
+        // identity point
+        let mut bad = params.clone();
+        bad.gs[7] = G1Projective::identity();
+        assert!(matches!(bad.verify(&mut rng), Err(KZGError::InvalidPoint)));
+    }
+
+    #[test]
+    fn test_fast_setup() {
+        let s = Scalar::from(0x1234_5678_9abc_def0);
+        let fast = setup_asymmetric(s, 33, 5).unwrap();
+        assert_eq!(fast.gs, powers_of(G1Projective::generator(), s, 33));
+        assert_eq!(fast.hs, powers_of(G2Projective::generator(), s, 5));
+        assert_eq!(setup(s, 7).hs, powers_of(G2Projective::generator(), s, 7));
+
+        assert!(matches!(setup_asymmetric(s, 0, 2), Err(KZGError::MalformedParams)));
+        assert!(matches!(setup_asymmetric(s, 2, 0), Err(KZGError::MalformedParams)));
+    }
 }
@@ -0,0 +1,285 @@
+//! Memory-mapped SRS backend for very large parameter sets.
+//!
+//! `MmapParams` maps a file holding the points in exactly the layout `blstrs` uses in memory, so
+//! `g1_powers()`/`g2_powers()` are slices straight into the mapping. Nothing is decoded up front,
+//! only the pages a prover or verifier actually touches are read, and every process mapping the
+//! same file shares one copy through the page cache.
+//!
+//! The file layout is:
+//!
+//! | field    | size              | description                                  |
+//! |----------|-------------------|----------------------------------------------|
+//! | magic    | 4                 | `b"KZGR"`                                    |
+//! | version  | 4                 | format version, little-endian. Currently `1` |
+//! | curve    | 4                 | curve id, little-endian. `1` is BLS12-381    |
+//! | padding  | 4                 | zero                                         |
+//! | num_g1   | 8                 | number of G1 points, little-endian           |
+//! | num_g2   | 8                 | number of G2 points, little-endian           |
+//! | gs       | 144 * num_g1      | raw `G1Projective`s                          |
+//! | hs       | 288 * num_g2      | raw `G2Projective`s                          |
+//!
+//! The points are raw Montgomery-form limbs in native byte order, so a file is only portable
+//! between machines with the same endianness. Use `serialization` for interchange.
+
+use blstrs::{G1Projective, G2Projective};
+use memmap2::Mmap;
+use pairing::group::Curve;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::mem::{align_of, size_of};
+use std::path::Path;
+use std::slice;
+
+use crate::serialization::CURVE_BLS12_381;
+use crate::{check_g2_powers, KZGError, KZGParams, SrsBackend};
+
+pub const RAW_PARAMS_MAGIC: &[u8; 4] = b"KZGR";
+pub const RAW_PARAMS_VERSION: u32 = 1;
+
+const HEADER_SIZE: usize = 32;
+const G1_RAW_SIZE: usize = 144;
+const G2_RAW_SIZE: usize = 288;
+
+/// powers of tau read directly out of a memory-mapped file. See the module docs for the format.
+#[derive(Debug)]
+pub struct MmapParams {
+    mmap: Mmap,
+    num_g1: usize,
+    num_g2: usize,
+}
+
+fn check_layout() {
+    // `G1Projective`/`G2Projective` are `repr(transparent)` wrappers around blst's `repr(C)` point
+    // structs, which are nothing but `u64` limbs. These catch a change in that representation.
+    assert_eq!(size_of::<G1Projective>(), G1_RAW_SIZE);
+    assert_eq!(size_of::<G2Projective>(), G2_RAW_SIZE);
+    assert_eq!(HEADER_SIZE % align_of::<G1Projective>(), 0);
+    assert_eq!(G1_RAW_SIZE % align_of::<G2Projective>(), 0);
+}
+
+impl MmapParams {
+    /// writes `params` to `path` in the raw format `open` expects
+    pub fn create<P: AsRef<Path>>(path: P, params: &KZGParams) -> Result<(), KZGError> {
+        check_layout();
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(RAW_PARAMS_MAGIC)?;
+        writer.write_all(&RAW_PARAMS_VERSION.to_le_bytes())?;
+        writer.write_all(&CURVE_BLS12_381.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&(params.gs.len() as u64).to_le_bytes())?;
+        writer.write_all(&(params.hs.len() as u64).to_le_bytes())?;
+
+        // SAFETY: see `check_layout` - the points are plain old data with no padding
+        let gs = unsafe {
+            slice::from_raw_parts(params.gs.as_ptr() as *const u8, params.gs.len() * G1_RAW_SIZE)
+        };
+        let hs = unsafe {
+            slice::from_raw_parts(params.hs.as_ptr() as *const u8, params.hs.len() * G2_RAW_SIZE)
+        };
+        writer.write_all(gs)?;
+        writer.write_all(hs)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// maps the file at `path`, checking only the header and length. Use `open_checked` unless
+    /// opening a large file in full is too slow.
+    ///
+    /// # Safety
+    ///
+    /// The points in the file are used as they are, so every one of them must be a valid
+    /// `G1Projective`/`G2Projective` on the curve and in the prime-order subgroup, i.e. the file
+    /// must have been written by `create` on a machine with the same endianness. The file must not
+    /// be modified while the returned `MmapParams` is alive.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<MmapParams, KZGError> {
+        Self::map(path)
+    }
+
+    /// like `open`, but also checks that every point is on the curve and in the prime-order
+    /// subgroup. This touches the whole file once.
+    ///
+    /// # Safety
+    ///
+    /// The check only covers the file as it is when `open_checked` runs. The file must not be
+    /// truncated or otherwise modified while the returned `MmapParams` is alive.
+    pub unsafe fn open_checked<P: AsRef<Path>>(path: P) -> Result<MmapParams, KZGError> {
+        let params = Self::map(path)?;
+
+        let g1_ok = params.g1_powers().iter().all(|g| {
+            let g = g.to_affine();
+            bool::from(g.is_on_curve() & g.is_torsion_free())
+        });
+        let g2_ok = params.g2_powers().iter().all(|h| {
+            let h = h.to_affine();
+            bool::from(h.is_on_curve() & h.is_torsion_free())
+        });
+        if g1_ok && g2_ok {
+            Ok(params)
+        } else {
+            Err(KZGError::InvalidPoint)
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Same as `open`: the file must not be modified while the returned `MmapParams` is alive.
+    unsafe fn map<P: AsRef<Path>>(path: P) -> Result<MmapParams, KZGError> {
+        check_layout();
+
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only, and callers of `open` and `open_checked` promise not to
+        // modify the file while it's mapped
+        let mmap = Mmap::map(&file)?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[..4] != RAW_PARAMS_MAGIC {
+            return Err(KZGError::MalformedSetupFile("bad magic".to_string()));
+        }
+        let u32_at = |i: usize| {
+            let mut buf = [0u8; 4];
+            buf.copy_from_slice(&mmap[i..i + 4]);
+            u32::from_le_bytes(buf)
+        };
+        let u64_at = |i: usize| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&mmap[i..i + 8]);
+            u64::from_le_bytes(buf) as usize
+        };
+
+        if u32_at(4) != RAW_PARAMS_VERSION {
+            return Err(KZGError::MalformedSetupFile(format!("unsupported version {}", u32_at(4))));
+        }
+        if u32_at(8) != CURVE_BLS12_381 {
+            return Err(KZGError::MalformedSetupFile(format!("unsupported curve {}", u32_at(8))));
+        }
+
+        let num_g1 = u64_at(16);
+        let num_g2 = u64_at(24);
+        let expected_len = num_g1
+            .checked_mul(G1_RAW_SIZE)
+            .and_then(|g1| num_g2.checked_mul(G2_RAW_SIZE).and_then(|g2| g1.checked_add(g2)))
+            .and_then(|points| points.checked_add(HEADER_SIZE));
+        if expected_len != Some(mmap.len()) {
+            return Err(KZGError::MalformedSetupFile(
+                "file length doesn't match header".to_string(),
+            ));
+        }
+
+        Ok(MmapParams {
+            mmap,
+            num_g1,
+            num_g2,
+        })
+    }
+
+    /// copies the first `num_g1` and `num_g2` powers into an in-memory `KZGParams`. Returns
+    /// `KZGError::PolynomialDegreeTooLarge` or `KZGError::NotEnoughG2Powers` if the file holds fewer.
+    pub fn to_params(&self, num_g1: usize, num_g2: usize) -> Result<KZGParams, KZGError> {
+        if num_g1 > self.num_g1 {
+            return Err(KZGError::PolynomialDegreeTooLarge);
+        }
+        check_g2_powers(self, num_g2)?;
+
+        Ok(KZGParams {
+            gs: self.g1_powers()[..num_g1].to_vec(),
+            hs: self.g2_powers()[..num_g2].to_vec(),
+        })
+    }
+}
+
+impl SrsBackend for MmapParams {
+    fn g1_powers(&self) -> &[G1Projective] {
+        let bytes = &self.mmap[HEADER_SIZE..HEADER_SIZE + self.num_g1 * G1_RAW_SIZE];
+        // SAFETY: `open` checked the length, the mapping is page-aligned and the header keeps the
+        // points aligned (see `check_layout`). Any bit pattern is a valid value of the limb arrays.
+        unsafe { slice::from_raw_parts(bytes.as_ptr() as *const G1Projective, self.num_g1) }
+    }
+
+    fn g2_powers(&self) -> &[G2Projective] {
+        let start = HEADER_SIZE + self.num_g1 * G1_RAW_SIZE;
+        let bytes = &self.mmap[start..start + self.num_g2 * G2_RAW_SIZE];
+        // SAFETY: same as above
+        unsafe { slice::from_raw_parts(bytes.as_ptr() as *const G2Projective, self.num_g2) }
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::coeff_form::{KZGProver, KZGVerifier};
+    use crate::polynomial::Polynomial;
+    use crate::setup;
+    use blstrs::Scalar;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use std::env;
+
+    const RNG_SEED: [u8; 32] = [69; 32];
+
+    #[test]
+    fn test_mmap_prover() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup(s, 16);
+
+        let path = env::temp_dir().join(format!("kzg_test_mmap_{}.bin", std::process::id()));
+        MmapParams::create(&path, &params).unwrap();
+        // SAFETY: nothing else touches the file
+        let mmap_params = unsafe { MmapParams::open_checked(&path) }.unwrap();
+        assert_eq!(mmap_params.g1_powers(), params.gs.as_slice());
+        assert_eq!(mmap_params.g2_powers(), params.hs.as_slice());
+
+        let copy = mmap_params.to_params(8, 2).unwrap();
+        assert_eq!(copy.gs, params.gs[..8]);
+        assert_eq!(copy.hs, params.hs[..2]);
+        assert!(matches!(mmap_params.to_params(17, 2), Err(KZGError::PolynomialDegreeTooLarge)));
+        assert!(matches!(
+            mmap_params.to_params(8, 17),
+            Err(KZGError::NotEnoughG2Powers { needed: 17, available: 16 })
+        ));
+
+        let coeffs = (0..10).map(|_| rng.gen::<u64>().into()).collect();
+        let polynomial = Polynomial::new(coeffs);
+
+        let prover = KZGProver::new(&mmap_params);
+        let verifier = KZGVerifier::new(&mmap_params).unwrap();
+        let commitment = prover.commit(&polynomial, None).unwrap().0;
+        assert_eq!(commitment, KZGProver::new(&params).commit(&polynomial, None).unwrap().0);
+
+        let x: Scalar = rng.gen::<u64>().into();
+        let y = polynomial.eval(x);
+        let witness = prover.create_witness(&polynomial, (x, y), None).unwrap().0;
+        assert!(verifier.verify_eval((x, y), &commitment, &witness, None));
+
+        drop(mmap_params);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mmap_bad_length() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup(s, 4);
+
+        let path = env::temp_dir().join(format!("kzg_test_mmap_bad_{}.bin", std::process::id()));
+        MmapParams::create(&path, &params).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let mut truncated = bytes.clone();
+        truncated.truncate(bytes.len() - 1);
+        std::fs::write(&path, &truncated).unwrap();
+        // SAFETY: nothing else touches the file
+        assert!(matches!(
+            unsafe { MmapParams::open_checked(&path) },
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+
+        // a G1 point that's no longer on the curve
+        let mut corrupted = bytes;
+        corrupted[HEADER_SIZE + G1_RAW_SIZE + 1] ^= 1;
+        std::fs::write(&path, &corrupted).unwrap();
+        assert!(matches!(unsafe { MmapParams::open_checked(&path) }, Err(KZGError::InvalidPoint)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -307,8 +307,10 @@ pub struct SubProductTree {
 }
 
 impl SubProductTree {
+    /// `xs` must not be empty
     pub fn new_from_points(xs: &[Scalar]) -> SubProductTree {
         match xs.len() {
+            0 => panic!("a subproduct tree needs at least one point"),
             1 => SubProductTree {
                 product: Polynomial::new_from_coeffs(vec![-xs[0], Scalar::one()], 1),
                 left: None,
@@ -391,7 +393,7 @@ where
     op_tree_inner(0, size, get_elem, op)
 }
 
-impl<'a> Add for &'a Polynomial {
+impl Add for &Polynomial {
     type Output = Polynomial;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -440,7 +442,7 @@ impl<R: Borrow<Polynomial>> AddAssign<R> for Polynomial {
     }
 }
 
-impl<'a> Sub for &'a Polynomial {
+impl Sub for &Polynomial {
     type Output = Polynomial;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -597,16 +599,8 @@ mod tests {
     }
 
     fn verify_tree(tree: &SubProductTree) {
-        if tree.left.is_some() && tree.right.is_some() {
-            assert!(
-                tree.product
-                    == tree
-                        .left
-                        .as_ref()
-                        .unwrap()
-                        .product
-                        .best_mul(&tree.right.as_ref().unwrap().product)
-            );
+        if let (Some(left), Some(right)) = (tree.left.as_ref(), tree.right.as_ref()) {
+            assert!(tree.product == left.product.best_mul(&right.product));
         }
     }
 
@@ -652,12 +646,6 @@ mod tests {
 
         let mut fast = polynomial.multi_eval(xs.as_slice());
         fast.truncate(xs.len());
-        let mut slow = Vec::new();
-
-        for i in 0..xs.len() {
-            let slow_y = polynomial.eval(xs[i]);
-            slow.push(slow_y);
-        }
 
         let slow: Vec<Scalar> = xs.iter().map(|x| polynomial.eval(*x)).collect();
         assert!(fast == slow);
@@ -692,7 +680,7 @@ mod tests {
     #[cfg(feature = "serde_support")]
     use bincode::{deserialize, serialize};
 
-    #[cfg(all(feature = "serde_support", feature = "b12_381"))]
+    #[cfg(feature = "serde_support")]
     #[test]
     fn test_polynomial_serialization() {
         let f = Polynomial::new(vec![
@@ -702,10 +690,8 @@ mod tests {
             4.into(),
         ]);
 
-        let serable = SerializablePolynomial::from_inner_ref(&f);
-        let ser = serialize(&serable).unwrap();
-        let de: SerializablePolynomial<Scalar> = deserialize(ser.as_slice()).unwrap();
-        let f_de: Polynomial<Scalar> = de.into();
+        let ser = serialize(&f).unwrap();
+        let f_de: Polynomial = deserialize(ser.as_slice()).unwrap();
         assert_eq!(f, f_de);
     }
 }
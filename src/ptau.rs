@@ -0,0 +1,526 @@
+//! Reader for snarkjs / perpetual powers-of-tau `.ptau` files over BLS12-381.
+//!
+//! A `.ptau` file starts with the magic `ptau`, a version and the number of sections, followed by
+//! sections of the form `(type: u32, size: u64, data)`. All integers are little-endian. We use
+//! - section 1: the header (`n8`, the base field modulus `q`, `power` and `ceremonyPower`)
+//! - section 2: `2^(power + 1) - 1` tau powers in G1
+//! - section 3: `2^power` tau powers in G2
+//! - sections 12 and 13: the Lagrange bases in G1 and G2 for every domain size `2^0..=2^power`,
+//!   concatenated. These are only present in files prepared for phase 2.
+//!
+//! Points are stored uncompressed, one little-endian Montgomery-form `n8`-byte integer per
+//! coordinate (`x.c0, x.c1, y.c0, y.c1` in G2), with the point at infinity encoded as all zeroes.
+
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use pairing::group::ff::Field;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::ft::EvaluationDomain;
+use crate::utils::is_power_of_two;
+use crate::{KZGError, KZGParams};
+
+const MAGIC: &[u8; 4] = b"ptau";
+const N8: usize = 48;
+const G1_SIZE: usize = 2 * N8;
+const G2_SIZE: usize = 4 * N8;
+
+/// the largest `power` we accept, as in snarkjs
+const MAX_POWER: u32 = 28;
+/// the size of a section's `(type, size)` header
+const SECTION_HEADER_SIZE: u64 = 12;
+
+const SECTION_HEADER: u32 = 1;
+const SECTION_TAU_G1: u32 = 2;
+const SECTION_TAU_G2: u32 = 3;
+const SECTION_LAGRANGE_G1: u32 = 12;
+const SECTION_LAGRANGE_G2: u32 = 13;
+
+/// the BLS12-381 base field modulus, as little-endian limbs
+const MODULUS: [u64; 6] = [
+    0xb9fe_ffff_ffff_aaab,
+    0x1eab_fffe_b153_ffff,
+    0x6730_d2a0_f6b0_f624,
+    0x6477_4b84_f385_12bf,
+    0x4b1b_a7b6_434b_acd7,
+    0x1a01_11ea_397f_e69a,
+];
+/// -MODULUS^{-1} mod 2^64
+const INV: u64 = 0x89f3_fffc_fffc_fffd;
+
+/// parameters read from a `.ptau` file
+#[derive(Clone, Debug)]
+pub struct PtauSetup {
+    pub params: KZGParams,
+    /// Lagrange basis over the domain of size `params.gs.len()`, if it was requested
+    pub lagrange_basis_g: Option<Vec<G1Projective>>,
+    pub lagrange_basis_h: Option<Vec<G2Projective>>,
+}
+
+/// reads the first `num_coeffs` powers from the `.ptau` file at `path`. See `read_ptau`.
+pub fn load_ptau<P: AsRef<Path>>(
+    path: P,
+    num_coeffs: usize,
+    with_lagrange: bool,
+) -> Result<PtauSetup, KZGError> {
+    read_ptau(BufReader::new(File::open(path)?), num_coeffs, with_lagrange)
+}
+
+/// reads the first `num_coeffs` powers of tau in both G1 and G2 from a `.ptau` file.
+/// If `with_lagrange` is set, `num_coeffs` must be a power of two and the file must contain the
+/// Lagrange sections. The Lagrange bases are checked to be over the same roots of unity as
+/// `EvaluationDomain`, so they can be handed to `KZGProverEvalForm` and `KZGVerifierEvalForm`.
+pub fn read_ptau<R: Read + Seek>(
+    mut reader: R,
+    num_coeffs: usize,
+    with_lagrange: bool,
+) -> Result<PtauSetup, KZGError> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(malformed("not a ptau file"));
+    }
+    let _version = read_u32(&mut reader)?;
+    let num_sections = read_u32(&mut reader)?;
+
+    if num_sections as u64 > file_len / SECTION_HEADER_SIZE {
+        return Err(malformed("more sections than fit in the file"));
+    }
+
+    // (type, offset, size) of every section
+    let mut sections = Vec::with_capacity(num_sections as usize);
+    for _ in 0..num_sections {
+        let section_type = read_u32(&mut reader)?;
+        let size = read_u64(&mut reader)?;
+        let offset = reader.stream_position()?;
+        if size > file_len.saturating_sub(offset) {
+            return Err(malformed(&format!("section {} extends past the end of the file", section_type)));
+        }
+        sections.push((section_type, offset, size));
+        reader.seek(SeekFrom::Start(offset + size))?;
+    }
+    let find = |section_type: u32| {
+        sections
+            .iter()
+            .find(|(t, _, _)| *t == section_type)
+            .map(|&(_, offset, size)| (offset, size))
+            .ok_or_else(|| malformed(&format!("missing section {}", section_type)))
+    };
+
+    let (offset, _) = find(SECTION_HEADER)?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let n8 = read_u32(&mut reader)? as usize;
+    if n8 != N8 {
+        return Err(malformed("only BLS12-381 ptau files are supported"));
+    }
+    let mut q = [0u8; N8];
+    reader.read_exact(&mut q)?;
+    if bytes_to_limbs(&q) != MODULUS {
+        return Err(malformed("only BLS12-381 ptau files are supported"));
+    }
+    let power = read_u32(&mut reader)?;
+    if power > MAX_POWER {
+        return Err(malformed(&format!("power {} is larger than {}", power, MAX_POWER)));
+    }
+    let max_coeffs = 1usize
+        .checked_shl(power)
+        .ok_or_else(|| malformed("power doesn't fit in usize"))?;
+    if num_coeffs < 2 || num_coeffs > max_coeffs {
+        return Err(malformed(&format!(
+            "requested {} coefficients but the file has 2^{} powers",
+            num_coeffs, power
+        )));
+    }
+
+    let (offset, size) = find(SECTION_TAU_G1)?;
+    if size != section_size(2 * max_coeffs - 1, G1_SIZE)? {
+        return Err(malformed("tauG1 section size doesn't match header"));
+    }
+    reader.seek(SeekFrom::Start(offset))?;
+    let gs = read_points(&mut reader, num_coeffs, read_g1)?;
+
+    let (offset, size) = find(SECTION_TAU_G2)?;
+    if size != section_size(max_coeffs, G2_SIZE)? {
+        return Err(malformed("tauG2 section size doesn't match header"));
+    }
+    reader.seek(SeekFrom::Start(offset))?;
+    let hs = read_points(&mut reader, num_coeffs, read_g2)?;
+
+    let (lagrange_basis_g, lagrange_basis_h) = if with_lagrange {
+        if !is_power_of_two(num_coeffs as u64) {
+            return Err(malformed("lagrange basis requires a power of two number of coefficients"));
+        }
+        let (_, _, omega) = EvaluationDomain::compute_omega(num_coeffs)?;
+        let omegas: Vec<Scalar> = (0..num_coeffs)
+            .map(|i| omega.pow_vartime([i as u64]))
+            .collect();
+
+        // the basis for 2^k points comes after the bases for 1, 2, ..., 2^(k-1) points
+        let (offset, size) = find(SECTION_LAGRANGE_G1)?;
+        if size != section_size(2 * max_coeffs - 1, G1_SIZE)? {
+            return Err(malformed("lagrange G1 section size doesn't match header"));
+        }
+        reader.seek(SeekFrom::Start(offset + section_size(num_coeffs - 1, G1_SIZE)?))?;
+        let lagrange_g = read_points(&mut reader, num_coeffs, read_g1)?;
+
+        let (offset, size) = find(SECTION_LAGRANGE_G2)?;
+        if size != section_size(2 * max_coeffs - 1, G2_SIZE)? {
+            return Err(malformed("lagrange G2 section size doesn't match header"));
+        }
+        reader.seek(SeekFrom::Start(offset + section_size(num_coeffs - 1, G2_SIZE)?))?;
+        let lagrange_h = read_points(&mut reader, num_coeffs, read_g2)?;
+
+        // sum_i omega^i L_i(X) = X, so this fails if the basis is over different roots of unity
+        if G1Projective::multi_exp(&lagrange_g, &omegas) != gs[1]
+            || G2Projective::multi_exp(&lagrange_h, &omegas) != hs[1]
+        {
+            return Err(malformed("lagrange basis is not over the expected domain"));
+        }
+
+        (Some(lagrange_g), Some(lagrange_h))
+    } else {
+        (None, None)
+    };
+
+    Ok(PtauSetup {
+        params: KZGParams { gs, hs },
+        lagrange_basis_g,
+        lagrange_basis_h,
+    })
+}
+
+fn malformed(msg: &str) -> KZGError {
+    KZGError::MalformedSetupFile(msg.to_string())
+}
+
+/// the size in bytes of `num_points` points of `point_size` bytes each
+fn section_size(num_points: usize, point_size: usize) -> Result<u64, KZGError> {
+    num_points
+        .checked_mul(point_size)
+        .map(|size| size as u64)
+        .ok_or_else(|| malformed("section size overflows"))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, KZGError> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, KZGError> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_points<R: Read, T>(
+    reader: &mut R,
+    n: usize,
+    read_point: fn(&mut R) -> Result<T, KZGError>,
+) -> Result<Vec<T>, KZGError> {
+    (0..n).map(|_| read_point(reader)).collect()
+}
+
+fn bytes_to_limbs(bytes: &[u8]) -> [u64; 6] {
+    let mut limbs = [0u64; 6];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(buf);
+    }
+    limbs
+}
+
+/// reads one little-endian Montgomery-form coordinate and returns it as canonical big-endian bytes
+fn read_coordinate<R: Read>(reader: &mut R) -> Result<[u8; N8], KZGError> {
+    let mut buf = [0u8; N8];
+    reader.read_exact(&mut buf)?;
+    let limbs = from_montgomery(&bytes_to_limbs(&buf));
+
+    let mut out = [0u8; N8];
+    for (i, limb) in limbs.iter().rev().enumerate() {
+        out[i * 8..(i + 1) * 8].copy_from_slice(&limb.to_be_bytes());
+    }
+    Ok(out)
+}
+
+fn read_g1<R: Read>(reader: &mut R) -> Result<G1Projective, KZGError> {
+    let x = read_coordinate(reader)?;
+    let y = read_coordinate(reader)?;
+
+    let mut bytes = [0u8; G1_SIZE];
+    if x.iter().chain(y.iter()).all(|&b| b == 0) {
+        // infinity flag
+        bytes[0] = 0x40;
+    } else {
+        bytes[..N8].copy_from_slice(&x);
+        bytes[N8..].copy_from_slice(&y);
+    }
+
+    // `from_uncompressed` checks both that the point is on the curve and in the subgroup
+    Option::from(G1Affine::from_uncompressed(&bytes))
+        .map(|p: G1Affine| p.into())
+        .ok_or(KZGError::InvalidPoint)
+}
+
+fn read_g2<R: Read>(reader: &mut R) -> Result<G2Projective, KZGError> {
+    let x_c0 = read_coordinate(reader)?;
+    let x_c1 = read_coordinate(reader)?;
+    let y_c0 = read_coordinate(reader)?;
+    let y_c1 = read_coordinate(reader)?;
+
+    // the zcash encoding puts c1 before c0
+    let mut bytes = [0u8; G2_SIZE];
+    let coords = [x_c1, x_c0, y_c1, y_c0];
+    if coords.iter().all(|c| c.iter().all(|&b| b == 0)) {
+        bytes[0] = 0x40;
+    } else {
+        for (i, c) in coords.iter().enumerate() {
+            bytes[i * N8..(i + 1) * N8].copy_from_slice(c);
+        }
+    }
+
+    Option::from(G2Affine::from_uncompressed(&bytes))
+        .map(|p: G2Affine| p.into())
+        .ok_or(KZGError::InvalidPoint)
+}
+
+/// computes a * b * R^{-1} mod MODULUS, where R = 2^384
+fn mont_mul(a: &[u64; 6], b: &[u64; 6]) -> [u64; 6] {
+    // CIOS montgomery multiplication
+    let mut t = [0u64; 8];
+    for &bi in b.iter() {
+        let mut carry = 0u128;
+        for j in 0..6 {
+            let tmp = t[j] as u128 + (a[j] as u128) * (bi as u128) + carry;
+            t[j] = tmp as u64;
+            carry = tmp >> 64;
+        }
+        let tmp = t[6] as u128 + carry;
+        t[6] = tmp as u64;
+        t[7] = (tmp >> 64) as u64;
+
+        let m = t[0].wrapping_mul(INV);
+        let mut carry = (t[0] as u128 + (m as u128) * (MODULUS[0] as u128)) >> 64;
+        for j in 1..6 {
+            let tmp = t[j] as u128 + (m as u128) * (MODULUS[j] as u128) + carry;
+            t[j - 1] = tmp as u64;
+            carry = tmp >> 64;
+        }
+        let tmp = t[6] as u128 + carry;
+        t[5] = tmp as u64;
+        t[6] = t[7] + (tmp >> 64) as u64;
+    }
+
+    let mut res = [0u64; 6];
+    res.copy_from_slice(&t[..6]);
+    if t[6] != 0 || !less_than_modulus(&res) {
+        let mut borrow = 0u128;
+        for j in 0..6 {
+            let tmp = (res[j] as u128).wrapping_sub(MODULUS[j] as u128 + borrow);
+            res[j] = tmp as u64;
+            borrow = (tmp >> 127) & 1;
+        }
+    }
+    res
+}
+
+fn less_than_modulus(a: &[u64; 6]) -> bool {
+    for j in (0..6).rev() {
+        if a[j] != MODULUS[j] {
+            return a[j] < MODULUS[j];
+        }
+    }
+    false
+}
+
+fn from_montgomery(a: &[u64; 6]) -> [u64; 6] {
+    mont_mul(a, &[1, 0, 0, 0, 0, 0])
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::eval_form::compute_lagrange_basis;
+    use crate::setup;
+    use pairing::group::Curve;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use std::io::Cursor;
+
+    const RNG_SEED: [u8; 32] = [69; 32];
+
+    /// R^2 mod MODULUS
+    const R2: [u64; 6] = [
+        0xf4df_1f34_1c34_1746,
+        0x0a76_e6a6_09d1_04f1,
+        0x8de5_476c_4c95_b6d5,
+        0x67eb_88a9_939d_83c0,
+        0x9a79_3e85_b519_952d,
+        0x1198_8fe5_92ca_e3aa,
+    ];
+
+    fn write_coordinate(out: &mut Vec<u8>, be_bytes: &[u8]) {
+        let mut le = be_bytes.to_vec();
+        le.reverse();
+        let mont = mont_mul(&bytes_to_limbs(&le), &R2);
+        for limb in mont.iter() {
+            out.extend_from_slice(&limb.to_le_bytes());
+        }
+    }
+
+    fn write_g1(out: &mut Vec<u8>, p: &G1Projective) {
+        let bytes = p.to_affine().to_uncompressed();
+        write_coordinate(out, &bytes[..N8]);
+        write_coordinate(out, &bytes[N8..]);
+    }
+
+    fn write_g2(out: &mut Vec<u8>, p: &G2Projective) {
+        let bytes = p.to_affine().to_uncompressed();
+        for &i in [1, 0, 3, 2].iter() {
+            write_coordinate(out, &bytes[i * N8..(i + 1) * N8]);
+        }
+    }
+
+    fn write_section(out: &mut Vec<u8>, section_type: u32, data: Vec<u8>) {
+        out.extend_from_slice(&section_type.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        out.extend_from_slice(&data);
+    }
+
+    fn ptau_file(rng: &mut SmallRng, power: u32) -> (Vec<u8>, KZGParams) {
+        let n = 1usize << power;
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup(s, 2 * n - 1);
+
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&5u32.to_le_bytes());
+
+        let mut header = (N8 as u32).to_le_bytes().to_vec();
+        for limb in MODULUS.iter() {
+            header.extend_from_slice(&limb.to_le_bytes());
+        }
+        header.extend_from_slice(&power.to_le_bytes());
+        header.extend_from_slice(&power.to_le_bytes());
+        write_section(&mut out, SECTION_HEADER, header);
+
+        let mut tau_g1 = Vec::new();
+        params.gs.iter().for_each(|g| write_g1(&mut tau_g1, g));
+        write_section(&mut out, SECTION_TAU_G1, tau_g1);
+
+        let mut tau_g2 = Vec::new();
+        params.hs[..n].iter().for_each(|h| write_g2(&mut tau_g2, h));
+        write_section(&mut out, SECTION_TAU_G2, tau_g2);
+
+        let mut lagrange_g1 = Vec::new();
+        let mut lagrange_g2 = Vec::new();
+        for k in 0..=power {
+            let d = 1 << k;
+            if d == 1 {
+                write_g1(&mut lagrange_g1, &params.gs[0]);
+                write_g2(&mut lagrange_g2, &params.hs[0]);
+            } else {
+                let truncated = KZGParams {
+                    gs: params.gs[..d].to_vec(),
+                    hs: params.hs[..d].to_vec(),
+                };
+                let (gs, hs) = compute_lagrange_basis(&truncated).unwrap();
+                gs.iter().for_each(|g| write_g1(&mut lagrange_g1, g));
+                hs.iter().for_each(|h| write_g2(&mut lagrange_g2, h));
+            }
+        }
+        write_section(&mut out, SECTION_LAGRANGE_G1, lagrange_g1);
+        write_section(&mut out, SECTION_LAGRANGE_G2, lagrange_g2);
+
+        (out, params)
+    }
+
+    #[test]
+    fn test_from_montgomery() {
+        // R mod MODULUS is the montgomery form of one
+        let r = [
+            0x7609_0000_0002_fffd,
+            0xebf4_000b_c40c_0002,
+            0x5f48_9857_53c7_58ba,
+            0x77ce_5853_7052_5745,
+            0x5c07_1a97_a256_ec6d,
+            0x15f6_5ec3_fa80_e493,
+        ];
+        assert_eq!(from_montgomery(&r), [1, 0, 0, 0, 0, 0]);
+        assert_eq!(from_montgomery(&mont_mul(&[42, 0, 0, 0, 0, 0], &R2)), [42, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_read_ptau() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let (file, params) = ptau_file(&mut rng, 3);
+
+        let ptau = read_ptau(Cursor::new(&file), 6, false).unwrap();
+        assert_eq!(ptau.params.gs, params.gs[..6]);
+        assert_eq!(ptau.params.hs, params.hs[..6]);
+        assert!(ptau.lagrange_basis_g.is_none());
+
+        let ptau = read_ptau(Cursor::new(&file), 4, true).unwrap();
+        let (lagrange_g, lagrange_h) = compute_lagrange_basis(&ptau.params).unwrap();
+        assert_eq!(ptau.lagrange_basis_g.unwrap(), lagrange_g);
+        assert_eq!(ptau.lagrange_basis_h.unwrap(), lagrange_h);
+
+        // more coefficients than the file has
+        assert!(matches!(
+            read_ptau(Cursor::new(&file), 16, false),
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_ptau_bad_header() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let (file, _) = ptau_file(&mut rng, 2);
+
+        // a huge number of sections
+        let mut bad = file.clone();
+        bad[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read_ptau(Cursor::new(&bad), 4, false),
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+
+        // a header section that claims to be larger than the file
+        let mut bad = file.clone();
+        bad[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            read_ptau(Cursor::new(&bad), 4, false),
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+
+        // power = 64
+        let mut bad = file;
+        let power_offset = 12 + 12 + 4 + N8;
+        bad[power_offset..power_offset + 4].copy_from_slice(&64u32.to_le_bytes());
+        assert!(matches!(
+            read_ptau(Cursor::new(&bad), 4, false),
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_ptau_bad_point() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let (mut file, _) = ptau_file(&mut rng, 2);
+
+        // corrupt the y coordinate of the second tauG1 point. The header section comes first,
+        // at offset 12 + 12, followed by the tauG1 section header.
+        let tau_g1_offset = 12 + 12 + 4 + N8 + 8 + 12;
+        file[tau_g1_offset + G1_SIZE + N8] ^= 1;
+
+        assert!(matches!(
+            read_ptau(Cursor::new(&file), 4, false),
+            Err(KZGError::InvalidPoint)
+        ));
+    }
+}
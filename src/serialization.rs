@@ -0,0 +1,297 @@
+//! Versioned binary format for `KZGParams`.
+//!
+//! | field    | size            | description                                   |
+//! |----------|-----------------|-----------------------------------------------|
+//! | magic    | 4               | `b"KZGP"`                                     |
+//! | version  | 4               | format version, little-endian. Currently `1`  |
+//! | curve    | 4               | curve id, little-endian. `1` is BLS12-381     |
+//! | num_g1   | 8               | number of G1 points, little-endian            |
+//! | num_g2   | 8               | number of G2 points, little-endian            |
+//! | gs       | 48 * num_g1     | compressed G1 points                          |
+//! | hs       | 96 * num_g2     | compressed G2 points                          |
+//! | checksum | 32              | SHA-256 of everything above                   |
+
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Projective};
+use pairing::group::Curve;
+use sha2::{Digest, Sha256};
+use std::io::{self, Read, Write};
+
+use crate::{KZGError, KZGParams};
+
+pub const PARAMS_MAGIC: &[u8; 4] = b"KZGP";
+pub const PARAMS_VERSION: u32 = 1;
+pub const CURVE_BLS12_381: u32 = 1;
+
+const G1_COMPRESSED_SIZE: usize = 48;
+const G2_COMPRESSED_SIZE: usize = 96;
+
+/// hashes everything written through it
+pub(crate) struct HashingWriter<W> {
+    pub(crate) inner: W,
+    pub(crate) hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// appends the checksum of everything written so far
+    pub(crate) fn finish(mut self) -> Result<(), KZGError> {
+        let checksum = self.hasher.finalize();
+        self.inner.write_all(&checksum)?;
+        self.inner.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// hashes everything read through it
+pub(crate) struct HashingReader<R> {
+    pub(crate) inner: R,
+    pub(crate) hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// reads the checksum and compares it against everything read so far
+    pub(crate) fn finish(mut self) -> Result<(), KZGError> {
+        let expected = self.hasher.finalize();
+        let mut checksum = [0u8; 32];
+        self.inner.read_exact(&mut checksum)?;
+        if checksum[..] != expected[..] {
+            return Err(KZGError::MalformedSetupFile("checksum mismatch".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// writes `magic`, the format version and the curve id
+pub(crate) fn write_header<W: Write>(writer: &mut W, magic: &[u8; 4]) -> Result<(), KZGError> {
+    writer.write_all(magic)?;
+    writer.write_all(&PARAMS_VERSION.to_le_bytes())?;
+    writer.write_all(&CURVE_BLS12_381.to_le_bytes())?;
+    Ok(())
+}
+
+/// reads and checks a header written by `write_header`
+pub(crate) fn read_header<R: Read>(reader: &mut R, magic: &[u8; 4]) -> Result<(), KZGError> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    if &buf != magic {
+        return Err(KZGError::MalformedSetupFile("bad magic".to_string()));
+    }
+
+    reader.read_exact(&mut buf)?;
+    let version = u32::from_le_bytes(buf);
+    if version != PARAMS_VERSION {
+        return Err(KZGError::MalformedSetupFile(format!(
+            "unsupported version {}",
+            version
+        )));
+    }
+
+    reader.read_exact(&mut buf)?;
+    let curve = u32::from_le_bytes(buf);
+    if curve != CURVE_BLS12_381 {
+        return Err(KZGError::MalformedSetupFile(format!(
+            "unsupported curve {}",
+            curve
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn read_u64<R: Read>(reader: &mut R) -> Result<u64, KZGError> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn write_g1s<W: Write>(writer: &mut W, points: &[G1Projective]) -> Result<(), KZGError> {
+    let mut affine = vec![G1Affine::default(); points.len()];
+    G1Projective::batch_normalize(points, &mut affine);
+    for p in affine.iter() {
+        writer.write_all(&p.to_compressed())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_g2s<W: Write>(writer: &mut W, points: &[G2Projective]) -> Result<(), KZGError> {
+    let mut affine = vec![G2Affine::default(); points.len()];
+    G2Projective::batch_normalize(points, &mut affine);
+    for p in affine.iter() {
+        writer.write_all(&p.to_compressed())?;
+    }
+    Ok(())
+}
+
+/// reads `n` compressed G1 points. The subgroup check is skipped unless `checked` is set.
+pub(crate) fn read_g1s<R: Read>(reader: &mut R, n: usize, checked: bool) -> Result<Vec<G1Projective>, KZGError> {
+    // don't trust `n` for the allocation - a corrupt header would otherwise make us
+    // allocate arbitrary amounts of memory before failing
+    let mut points = Vec::new();
+    let mut bytes = [0u8; G1_COMPRESSED_SIZE];
+    for _ in 0..n {
+        reader.read_exact(&mut bytes)?;
+        let p = if checked {
+            G1Affine::from_compressed(&bytes)
+        } else {
+            G1Affine::from_compressed_unchecked(&bytes)
+        };
+        let p: Option<G1Affine> = p.into();
+        points.push(p.ok_or(KZGError::InvalidPoint)?.into());
+    }
+    Ok(points)
+}
+
+/// reads `n` compressed G2 points. The subgroup check is skipped unless `checked` is set.
+pub(crate) fn read_g2s<R: Read>(reader: &mut R, n: usize, checked: bool) -> Result<Vec<G2Projective>, KZGError> {
+    let mut points = Vec::new();
+    let mut bytes = [0u8; G2_COMPRESSED_SIZE];
+    for _ in 0..n {
+        reader.read_exact(&mut bytes)?;
+        let p = if checked {
+            G2Affine::from_compressed(&bytes)
+        } else {
+            G2Affine::from_compressed_unchecked(&bytes)
+        };
+        let p: Option<G2Affine> = p.into();
+        points.push(p.ok_or(KZGError::InvalidPoint)?.into());
+    }
+    Ok(points)
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl KZGParams {
+    /// writes the parameters in the format described in the module docs
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), KZGError> {
+        let mut writer = HashingWriter::new(writer);
+
+        write_header(&mut writer, PARAMS_MAGIC)?;
+        writer.write_all(&(self.gs.len() as u64).to_le_bytes())?;
+        writer.write_all(&(self.hs.len() as u64).to_le_bytes())?;
+        write_g1s(&mut writer, &self.gs)?;
+        write_g2s(&mut writer, &self.hs)?;
+
+        writer.finish()
+    }
+
+    /// reads parameters written by `write_to`, checking that every point is on the curve and in the
+    /// prime-order subgroup. Use this for files that come from somewhere you don't control.
+    pub fn read_from<R: Read>(reader: R) -> Result<KZGParams, KZGError> {
+        Self::read_from_inner(reader, true)
+    }
+
+    /// like `read_from`, but skips the (expensive) subgroup checks. Points are still checked to
+    /// decompress to points on the curve, and the checksum is still verified.
+    /// Only use this for files you wrote yourself.
+    pub fn read_from_unchecked<R: Read>(reader: R) -> Result<KZGParams, KZGError> {
+        Self::read_from_inner(reader, false)
+    }
+
+    fn read_from_inner<R: Read>(reader: R, checked: bool) -> Result<KZGParams, KZGError> {
+        let mut reader = HashingReader::new(reader);
+
+        read_header(&mut reader, PARAMS_MAGIC)?;
+        let num_g1 = read_u64(&mut reader)? as usize;
+        let num_g2 = read_u64(&mut reader)? as usize;
+        let gs = read_g1s(&mut reader, num_g1, checked)?;
+        let hs = read_g2s(&mut reader, num_g2, checked)?;
+
+        reader.finish()?;
+
+        Ok(KZGParams { gs, hs })
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::setup;
+    use blstrs::Scalar;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    const RNG_SEED: [u8; 32] = [69; 32];
+
+    fn test_params(rng: &mut SmallRng, num_coeffs: usize) -> KZGParams {
+        let s: Scalar = rng.gen::<u64>().into();
+        setup(s, num_coeffs)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_params(&mut rng, 16);
+
+        let mut bytes = Vec::new();
+        params.write_to(&mut bytes).unwrap();
+        assert_eq!(
+            bytes.len(),
+            4 + 4 + 4 + 8 + 8 + 16 * G1_COMPRESSED_SIZE + 16 * G2_COMPRESSED_SIZE + 32
+        );
+
+        let read = KZGParams::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read.gs, params.gs);
+        assert_eq!(read.hs, params.hs);
+
+        let read = KZGParams::read_from_unchecked(bytes.as_slice()).unwrap();
+        assert_eq!(read.gs, params.gs);
+        assert_eq!(read.hs, params.hs);
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let params = test_params(&mut rng, 4);
+
+        let mut bytes = Vec::new();
+        params.write_to(&mut bytes).unwrap();
+
+        let mut bad_checksum = bytes.clone();
+        *bad_checksum.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            KZGParams::read_from(bad_checksum.as_slice()),
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        assert!(KZGParams::read_from(bad_version.as_slice()).is_err());
+
+        let truncated = &bytes[..bytes.len() - 40];
+        assert!(matches!(
+            KZGParams::read_from(truncated),
+            Err(KZGError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+//! Fiat-Shamir transcripts for deriving challenges non-interactively.
+//!
+//! A `Transcript` is a running SHA-256 hash. Every value is absorbed together with a label and its
+//! length, so two different sequences of messages can't produce the same state. Challenges are
+//! squeezed out of the current state and then absorbed back in, so squeezing twice in a row gives
+//! two different challenges. Prover and verifier must absorb the same values in the same order.
+
+use blstrs::{G1Affine, Scalar};
+use rand_core::{impls, Error, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::ft::EvaluationDomain;
+use crate::polynomial::Polynomial;
+use crate::utils::hash_to_scalar;
+use crate::{KZGCommitment, KZGWitness};
+
+const PROTOCOL_LABEL: &[u8] = b"kzg-transcript-v1";
+
+#[derive(Clone, Debug)]
+pub struct Transcript {
+    hasher: Sha256,
+}
+
+impl Transcript {
+    /// starts a transcript for the protocol identified by `label`
+    pub fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Transcript {
+            hasher: Sha256::new(),
+        };
+        transcript.append_message(b"protocol", PROTOCOL_LABEL);
+        transcript.append_message(b"label", label);
+        transcript
+    }
+
+    /// absorbs arbitrary bytes
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.hasher.update((label.len() as u64).to_le_bytes());
+        self.hasher.update(label);
+        self.hasher.update((message.len() as u64).to_le_bytes());
+        self.hasher.update(message);
+    }
+
+    pub fn append_u64(&mut self, label: &'static [u8], n: u64) {
+        self.append_message(label, &n.to_le_bytes());
+    }
+
+    pub fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
+        self.append_message(label, &scalar.to_bytes_le());
+    }
+
+    pub fn append_scalars(&mut self, label: &'static [u8], scalars: &[Scalar]) {
+        self.append_u64(label, scalars.len() as u64);
+        for scalar in scalars {
+            self.append_scalar(label, scalar);
+        }
+    }
+
+    pub fn append_commitment(&mut self, label: &'static [u8], commitment: &KZGCommitment) {
+        self.append_point(label, commitment);
+    }
+
+    pub fn append_witness(&mut self, label: &'static [u8], witness: &KZGWitness) {
+        self.append_point(label, witness);
+    }
+
+    pub fn append_polynomial(&mut self, label: &'static [u8], polynomial: &Polynomial) {
+        self.append_scalars(label, polynomial.slice_coeffs());
+    }
+
+    /// absorbs the domain (size and coset shift) along with the evaluations
+    pub fn append_evaluation_domain(&mut self, label: &'static [u8], domain: &EvaluationDomain) {
+        self.append_u64(label, domain.d as u64);
+        self.append_scalar(label, &domain.shift);
+        self.append_scalars(label, domain.as_ref());
+    }
+
+    fn append_point(&mut self, label: &'static [u8], point: &G1Affine) {
+        self.append_message(label, &point.to_compressed());
+    }
+
+    /// squeezes a challenge out of everything absorbed so far
+    pub fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        self.append_message(b"challenge", label);
+        let challenge = hash_to_scalar(&self.hasher.clone().finalize());
+        self.append_scalar(b"challenge value", &challenge);
+        challenge
+    }
+
+    /// squeezes a seed out of everything absorbed so far and returns an RNG expanding it. Useful
+    /// for APIs that want random weights, e.g. `KZGVerifier::verify_eval_many`.
+    pub fn challenge_rng(&mut self, label: &'static [u8]) -> TranscriptRng {
+        let seed = self.challenge_scalar(label);
+        TranscriptRng {
+            seed: seed.to_bytes_le(),
+            counter: 0,
+        }
+    }
+}
+
+/// a deterministic RNG seeded from a `Transcript` - see `Transcript::challenge_rng`
+#[derive(Clone, Debug)]
+pub struct TranscriptRng {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl RngCore for TranscriptRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(32) {
+            let mut hasher = Sha256::new();
+            hasher.update(self.seed);
+            hasher.update(self.counter.to_le_bytes());
+            self.counter += 1;
+
+            let block = hasher.finalize();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pairing::group::{ff::Field, prime::PrimeCurveAffine};
+
+    #[test]
+    fn test_challenges() {
+        let mut a = Transcript::new(b"test");
+        let mut b = Transcript::new(b"test");
+        for t in [&mut a, &mut b].iter_mut() {
+            t.append_commitment(b"C", &G1Affine::generator());
+            t.append_scalar(b"x", &Scalar::from(7));
+        }
+
+        // same messages, same challenges - and squeezing twice gives different ones
+        let c1 = a.challenge_scalar(b"gamma");
+        assert_eq!(c1, b.challenge_scalar(b"gamma"));
+        assert_ne!(c1, a.challenge_scalar(b"gamma"));
+
+        // labels are part of the transcript
+        let mut c = Transcript::new(b"test");
+        c.append_commitment(b"C", &G1Affine::generator());
+        c.append_scalar(b"y", &Scalar::from(7));
+        assert_ne!(c.challenge_scalar(b"gamma"), c1);
+
+        // and so are protocol labels
+        let mut d = Transcript::new(b"other");
+        d.append_commitment(b"C", &G1Affine::generator());
+        d.append_scalar(b"x", &Scalar::from(7));
+        assert_ne!(d.challenge_scalar(b"gamma"), c1);
+
+        let mut rng = a.challenge_rng(b"weights");
+        assert_ne!(Scalar::random(&mut rng), Scalar::random(&mut rng));
+    }
+}
@@ -0,0 +1,319 @@
+//! Loader for the output of the Ethereum KZG ceremony.
+//!
+//! Two encodings of the same data are supported:
+//! - the `trusted_setup.txt` format used by c-kzg: the number of G1 points, the number of G2 points,
+//!   then the G1 Lagrange points, the G2 monomial points and the G1 monomial points, one hex-encoded
+//!   compressed point per line.
+//! - the JSON format used by the consensus specs, with `g1_monomial`, `g1_lagrange` and `g2_monomial`
+//!   arrays of `0x`-prefixed hex strings. Only available with the `json_setup` feature.
+//!
+//! The Lagrange points in both formats are stored in bit-reversed order. They are permuted back into
+//! the natural order of `EvaluationDomain::compute_omega`, whose roots of unity are the same as the
+//! ones used by the ceremony, and checked against the monomial points.
+
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use pairing::group::ff::Field;
+#[cfg(feature = "json_setup")]
+use serde_json::Value;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::ft::EvaluationDomain;
+use crate::utils::{bitreverse, decode_hex, is_power_of_two, log2};
+use crate::{KZGError, KZGParams};
+
+/// parameters and precomputed G1 Lagrange basis from the Ethereum KZG ceremony.
+/// `params.hs` only has as many powers as the file provides (65 for the mainnet setup).
+#[derive(Clone, Debug)]
+pub struct TrustedSetup {
+    pub params: KZGParams,
+    /// Lagrange basis over the domain of size `params.gs.len()`, in natural order.
+    /// Can be passed directly to `KZGProverEvalForm::new` and `KZGVerifierEvalForm::new`.
+    pub lagrange_basis_g: Vec<G1Projective>,
+}
+
+/// loads a trusted setup from `path`, detecting whether it's JSON or the c-kzg text format. JSON
+/// files are rejected unless the `json_setup` feature is enabled.
+pub fn load_trusted_setup<P: AsRef<Path>>(path: P) -> Result<TrustedSetup, KZGError> {
+    let contents = fs::read_to_string(path)?;
+    if !contents.trim_start().starts_with('{') {
+        return parse_trusted_setup_txt(&contents);
+    }
+
+    #[cfg(feature = "json_setup")]
+    return parse_trusted_setup_json(&contents);
+
+    #[cfg(not(feature = "json_setup"))]
+    Err(KZGError::MalformedSetupFile(
+        "JSON setups need the `json_setup` feature".to_string(),
+    ))
+}
+
+pub fn parse_trusted_setup_txt(contents: &str) -> Result<TrustedSetup, KZGError> {
+    let mut lines = contents.lines().map(str::trim).filter(|l| !l.is_empty());
+    let mut next_count = |name: &str| -> Result<usize, KZGError> {
+        lines
+            .next()
+            .and_then(|l| l.parse().ok())
+            .ok_or_else(|| KZGError::MalformedSetupFile(format!("missing number of {} points", name)))
+    };
+
+    let num_g1 = next_count("G1")?;
+    let num_g2 = next_count("G2")?;
+
+    let lines: Vec<&str> = lines.collect();
+    if lines.len() != 2 * num_g1 + num_g2 {
+        return Err(KZGError::MalformedSetupFile(format!(
+            "expected {} points, found {}",
+            2 * num_g1 + num_g2,
+            lines.len()
+        )));
+    }
+
+    let g1_lagrange = &lines[..num_g1];
+    let g2_monomial = &lines[num_g1..num_g1 + num_g2];
+    let g1_monomial = &lines[num_g1 + num_g2..];
+
+    build(g1_monomial, g1_lagrange, g2_monomial)
+}
+
+#[cfg(feature = "json_setup")]
+pub fn parse_trusted_setup_json(contents: &str) -> Result<TrustedSetup, KZGError> {
+    let json: Value = serde_json::from_str(contents)
+        .map_err(|e| KZGError::MalformedSetupFile(format!("invalid JSON: {}", e)))?;
+    let g1_monomial = json_string_array(&json, "g1_monomial")?;
+    let g1_lagrange = json_string_array(&json, "g1_lagrange")?;
+    let g2_monomial = json_string_array(&json, "g2_monomial")?;
+
+    build(&g1_monomial, &g1_lagrange, &g2_monomial)
+}
+
+fn build(
+    g1_monomial: &[&str],
+    g1_lagrange: &[&str],
+    g2_monomial: &[&str],
+) -> Result<TrustedSetup, KZGError> {
+    let d = g1_monomial.len();
+    if d < 2 || !is_power_of_two(d as u64) || g1_lagrange.len() != d {
+        return Err(KZGError::MalformedSetupFile(
+            "number of G1 points must be the same power of two for both bases".to_string(),
+        ));
+    }
+    if g2_monomial.len() < 2 {
+        return Err(KZGError::MalformedSetupFile(
+            "at least two G2 points are required".to_string(),
+        ));
+    }
+
+    let gs = g1_monomial
+        .iter()
+        .map(|s| decode_g1(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let hs = g2_monomial
+        .iter()
+        .map(|s| decode_g2(s))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let log_d = log2(d as u64) as u32;
+    let lagrange_basis_g = (0..d)
+        .map(|i| decode_g1(g1_lagrange[bitreverse(i, log_d)]))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // sum_i L_i(X) = 1 and sum_i omega^i L_i(X) = X, so this fails if the Lagrange points are over
+    // a different domain, in a different order, or for a different tau than the monomial points
+    let (_, _, omega) = EvaluationDomain::compute_omega(d)?;
+    let mut omegas = Vec::with_capacity(d);
+    let mut u = Scalar::one();
+    for _ in 0..d {
+        omegas.push(u);
+        u *= omega;
+    }
+    if G1Projective::multi_exp(&lagrange_basis_g, &vec![Scalar::one(); d]) != gs[0]
+        || G1Projective::multi_exp(&lagrange_basis_g, &omegas) != gs[1]
+    {
+        return Err(KZGError::MalformedSetupFile(
+            "lagrange points don't match the monomial points".to_string(),
+        ));
+    }
+
+    Ok(TrustedSetup {
+        params: KZGParams { gs, hs },
+        lagrange_basis_g,
+    })
+}
+
+fn decode_g1(s: &str) -> Result<G1Projective, KZGError> {
+    let bytes = decode_hex(s).ok_or_else(|| KZGError::MalformedSetupFile(format!("invalid hex: {}", s)))?;
+    let bytes: [u8; 48] = bytes.try_into().map_err(|_| KZGError::InvalidPoint)?;
+
+    // `from_compressed` checks both that the point is on the curve and in the subgroup
+    Option::from(G1Affine::from_compressed(&bytes))
+        .map(|p: G1Affine| p.into())
+        .ok_or(KZGError::InvalidPoint)
+}
+
+fn decode_g2(s: &str) -> Result<G2Projective, KZGError> {
+    let bytes = decode_hex(s).ok_or_else(|| KZGError::MalformedSetupFile(format!("invalid hex: {}", s)))?;
+    let bytes: [u8; 96] = bytes.try_into().map_err(|_| KZGError::InvalidPoint)?;
+
+    Option::from(G2Affine::from_compressed(&bytes))
+        .map(|p: G2Affine| p.into())
+        .ok_or(KZGError::InvalidPoint)
+}
+
+/// extracts the array of strings under `key`
+#[cfg(feature = "json_setup")]
+fn json_string_array<'a>(json: &'a Value, key: &str) -> Result<Vec<&'a str>, KZGError> {
+    let invalid = || KZGError::MalformedSetupFile(format!("\"{}\" must be an array of strings", key));
+
+    json.get(key)
+        .and_then(Value::as_array)
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|v| v.as_str().ok_or_else(invalid))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "json_setup")]
+    use crate::eval_form::{KZGProverEvalForm, KZGVerifierEvalForm};
+    use crate::setup;
+    use pairing::group::{Curve, Group};
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    const RNG_SEED: [u8; 32] = [69; 32];
+
+    type EncodedSetup = (Vec<String>, Vec<String>, Vec<String>);
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // returns (g1_monomial, g1_lagrange in bit-reversed order, g2_monomial) as hex strings,
+    // along with the lagrange basis in natural order. The basis is computed straight from
+    // L_i(s) = omega^i (s^d - 1) / (d (s - omega^i)) rather than with the FFT.
+    fn encode_setup(rng: &mut SmallRng, d: usize, num_g2: usize) -> (EncodedSetup, Vec<G1Projective>) {
+        let s: Scalar = rng.gen::<u64>().into();
+        let params = setup(s, d);
+        let (_, _, omega) = EvaluationDomain::compute_omega(d).unwrap();
+        let z = (s.pow_vartime([d as u64]) - Scalar::one()) * Scalar::from(d as u64).invert().unwrap();
+        let lagrange_g: Vec<G1Projective> = (0..d)
+            .map(|i| {
+                let omega_i = omega.pow_vartime([i as u64]);
+                G1Projective::generator() * (omega_i * z * (s - omega_i).invert().unwrap())
+            })
+            .collect();
+
+        let log_d = log2(d as u64) as u32;
+        let g1_monomial = params.gs.iter().map(|g| to_hex(&g.to_affine().to_compressed())).collect();
+        let g1_lagrange = (0..d)
+            .map(|i| to_hex(&lagrange_g[bitreverse(i, log_d)].to_affine().to_compressed()))
+            .collect();
+        let g2_monomial = params.hs[..num_g2]
+            .iter()
+            .map(|h| to_hex(&h.to_affine().to_compressed()))
+            .collect();
+
+        ((g1_monomial, g1_lagrange, g2_monomial), lagrange_g)
+    }
+
+    #[test]
+    fn test_parse_txt() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let ((g1_monomial, g1_lagrange, g2_monomial), lagrange_g) = encode_setup(&mut rng, 8, 3);
+
+        let mut lines = vec!["8".to_string(), "3".to_string()];
+        lines.extend(g1_lagrange);
+        lines.extend(g2_monomial);
+        lines.extend(g1_monomial);
+        let trusted_setup = parse_trusted_setup_txt(&lines.join("\n")).unwrap();
+
+        assert_eq!(trusted_setup.params.gs.len(), 8);
+        assert_eq!(trusted_setup.params.hs.len(), 3);
+        assert_eq!(trusted_setup.lagrange_basis_g, lagrange_g);
+    }
+
+    #[cfg(feature = "json_setup")]
+    #[test]
+    fn test_parse_json_and_prove() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let ((g1_monomial, g1_lagrange, g2_monomial), _) = encode_setup(&mut rng, 8, 2);
+
+        let array = |xs: Vec<String>| {
+            xs.iter().map(|x| format!("\"0x{}\"", x)).collect::<Vec<_>>().join(",\n")
+        };
+        let json = format!(
+            "{{\n\"g1_monomial\": [{}],\n\"g1_lagrange\": [{}],\n\"g2_monomial\": [{}]\n}}",
+            array(g1_monomial),
+            array(g1_lagrange),
+            array(g2_monomial)
+        );
+        let trusted_setup = parse_trusted_setup_json(&json).unwrap();
+
+        let prover = KZGProverEvalForm::new(&trusted_setup.params, &trusted_setup.lagrange_basis_g);
+        let verifier = KZGVerifierEvalForm::new(&trusted_setup.params, &trusted_setup.lagrange_basis_g, &[]).unwrap();
+
+        let coeffs = (0..8).map(|_| rng.gen::<u64>().into()).collect();
+        let evals = EvaluationDomain::from_coeffs(coeffs).unwrap();
+        let commitment = prover.commit(&evals).unwrap();
+        let witness = prover.create_witness(&evals, 5).unwrap();
+        assert!(verifier.verify_eval((5, evals.coeffs[5]), &commitment, &witness));
+    }
+
+    #[test]
+    fn test_invalid_point() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let ((g1_monomial, g1_lagrange, g2_monomial), _) = encode_setup(&mut rng, 4, 2);
+
+        let mut lines = vec!["4".to_string(), "2".to_string()];
+        lines.extend(g1_lagrange);
+        lines.extend(g2_monomial);
+        lines.extend(g1_monomial);
+        // flip a bit in the x coordinate of the last G1 point
+        let last = lines.pop().unwrap();
+        let flipped = format!("{}{:x}", &last[..95], u8::from_str_radix(&last[95..], 16).unwrap() ^ 1);
+        lines.push(flipped);
+
+        assert!(matches!(
+            parse_trusted_setup_txt(&lines.join("\n")),
+            Err(KZGError::InvalidPoint)
+        ));
+    }
+
+    #[test]
+    fn test_inconsistent_lagrange() {
+        let mut rng = SmallRng::from_seed(RNG_SEED);
+        let ((g1_monomial, mut g1_lagrange, g2_monomial), _) = encode_setup(&mut rng, 4, 2);
+        let ((_, other_lagrange, _), _) = encode_setup(&mut rng, 4, 2);
+
+        let parse = |g1_lagrange: &[String]| {
+            let mut lines = vec!["4".to_string(), "2".to_string()];
+            lines.extend(g1_lagrange.iter().cloned());
+            lines.extend(g2_monomial.iter().cloned());
+            lines.extend(g1_monomial.iter().cloned());
+            parse_trusted_setup_txt(&lines.join("\n"))
+        };
+
+        // a basis for a different tau, and the right basis in the wrong order
+        assert!(matches!(parse(&other_lagrange), Err(KZGError::MalformedSetupFile(_))));
+        g1_lagrange.swap(1, 2);
+        assert!(matches!(parse(&g1_lagrange), Err(KZGError::MalformedSetupFile(_))));
+    }
+
+    #[cfg(feature = "json_setup")]
+    #[test]
+    fn test_malformed_json() {
+        assert!(matches!(
+            parse_trusted_setup_json("{\"g1_monomial\": [\"0x00\""),
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+        assert!(matches!(
+            parse_trusted_setup_json("{\"g1_monomial\": [1, 2], \"g1_lagrange\": [], \"g2_monomial\": []}"),
+            Err(KZGError::MalformedSetupFile(_))
+        ));
+    }
+}
@@ -1,5 +1,6 @@
-use blstrs::{G1Affine, Scalar};
-use pairing::group::ff::PrimeField;
+use blstrs::Scalar;
+use pairing::group::ff::{Field, PrimeField};
+use sha2::{Digest, Sha256};
 
 // fast 64-bit log
 // copypasta from https://stackoverflow.com/questions/11376288/fast-computing-of-log2-for-64-bit-integers
@@ -51,3 +52,45 @@ pub fn chunk_by_num_threads(size: usize) -> usize {
 pub fn is_power_of_two(n: u64) -> bool {
     n & (n - 1) == 0
 }
+
+/// decodes a hex string, with or without a leading "0x"
+pub fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if s.len() % 2 == 1 {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// hashes `bytes` to a field element. 512 bits of SHA-256 output are reduced mod r, so the result
+/// is uniform up to a negligible bias.
+pub fn hash_to_scalar(bytes: &[u8]) -> Scalar {
+    let mut wide = Vec::with_capacity(64);
+    for counter in 0u8..2 {
+        let mut hasher = Sha256::new();
+        hasher.update([counter]);
+        hasher.update(bytes);
+        wide.extend_from_slice(&hasher.finalize());
+    }
+
+    let shift = Scalar::from(u64::MAX) + Scalar::one();
+    wide.chunks(8).fold(Scalar::zero(), |acc, limb| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(limb);
+        acc * shift + Scalar::from(u64::from_be_bytes(buf))
+    })
+}
+
+/// reverses the lowest `l` bits of `n`
+pub fn bitreverse(mut n: usize, l: u32) -> usize {
+    let mut r = 0;
+    for _ in 0..l {
+        r = (r << 1) | (n & 1);
+        n >>= 1;
+    }
+    r
+}